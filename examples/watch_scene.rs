@@ -0,0 +1,50 @@
+extern crate glium;
+extern crate glowygraph as gg;
+
+use gg::render2::*;
+use gg::scene::Scene;
+use gg::watch::SceneWatcher;
+use glium::glutin;
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: watch_scene <scene.dot|scene.json>");
+
+    let mut events_loop = glutin::EventsLoop::new();
+    let context = glutin::ContextBuilder::new().with_vsync(true);
+    let window_builder = glutin::WindowBuilder::new().with_title(format!("glowygraph - {}", path));
+    let display = glium::Display::new(window_builder, context, &events_loop).unwrap();
+    let glowy = Renderer::new(&display);
+
+    let mut scene = Scene::default();
+    let mut watcher = SceneWatcher::new(path);
+
+    loop {
+        if let Err(e) = watcher.poll(&mut scene) {
+            eprintln!("{}", e);
+        }
+        watcher.animate(&mut scene, 1.0 / 60.0);
+
+        // Get dimensions
+        let dims = display.get_framebuffer_dimensions();
+        let hscale = dims.1 as f32 / dims.0 as f32;
+
+        let mut target = display.draw();
+        scene.render(
+            &glowy,
+            &mut target,
+            [[0.9, 0.0, 0.0], [0.0, 0.9, 0.0], [0.0, 0.0, 1.0]],
+            [[hscale, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        );
+        target.finish().unwrap();
+
+        events_loop.poll_events(|event| match event {
+            glutin::Event::WindowEvent { event, .. } => match event {
+                glutin::WindowEvent::Closed => ::std::process::exit(0),
+                _ => (),
+            },
+            _ => (),
+        });
+    }
+}
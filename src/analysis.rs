@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+
+use layout::neighbors;
+use palette;
+use render2::Ribbon;
+use scale::Scale;
+use seed::Rng;
+use style::Style;
+
+/// Find communities of densely connected nodes with label propagation and get the community of every node.
+///
+/// Communities are numbered from the largest to the smallest, so the biggest ones get the first colors of the
+/// categorical palette. Isolated nodes each get a community of their own. The result is deterministic, since this is
+/// `communities_seeded` with a seed of zero.
+pub fn communities(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    communities_seeded(node_count, edges, 0)
+}
+
+/// Find communities like `communities`, where `seed` picks the order nodes are visited in.
+///
+/// The order can change how ties between communities are broken, and the same seed always gives the same result.
+pub fn communities_seeded(node_count: usize, edges: &[(usize, usize)], seed: u64) -> Vec<usize> {
+    let neighbors = neighbors(node_count, edges);
+    let mut labels = (0..node_count).collect::<Vec<_>>();
+
+    // Visit nodes in an order that is scattered across the graph, which avoids one label flooding everything.
+    let mut order = (0..node_count).collect::<Vec<_>>();
+    Rng::new(seed).shuffle(&mut order);
+
+    for _ in 0..100 {
+        let mut changed = false;
+        for &node in &order {
+            if neighbors[node].is_empty() {
+                continue;
+            }
+            let mut counts = HashMap::new();
+            for &neighbor in &neighbors[node] {
+                *counts.entry(labels[neighbor]).or_insert(0) += 1;
+            }
+            let most = *counts.values().max().unwrap();
+            // Keep the current label on ties so the labels settle, otherwise take the smallest of the most common.
+            if counts.get(&labels[node]) != Some(&most) {
+                labels[node] = *counts.iter().filter(|&(_, &c)| c == most).map(|(l, _)| l).min().unwrap();
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut sizes = HashMap::new();
+    for &label in &labels {
+        *sizes.entry(label).or_insert(0) += 1;
+    }
+    let mut ranked = sizes.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let ids = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(id, (label, _))| (label, id))
+        .collect::<HashMap<_, _>>();
+    labels.into_iter().map(|label| ids[&label]).collect()
+}
+
+/// Give every node a copy of `style` colored by its community from the categorical palette.
+pub fn color_by_community(node_count: usize, edges: &[(usize, usize)], style: &Style) -> Vec<Style> {
+    color_by_community_seeded(node_count, edges, style, 0)
+}
+
+/// Color nodes like `color_by_community`, where `seed` is passed on to `communities_seeded`.
+pub fn color_by_community_seeded(
+    node_count: usize,
+    edges: &[(usize, usize)],
+    style: &Style,
+    seed: u64,
+) -> Vec<Style> {
+    communities_seeded(node_count, edges, seed)
+        .into_iter()
+        .map(|community| style.with_color(palette::categorical(community)))
+        .collect()
+}
+
+/// Get the number of edges connected to every node as a metric for `size_by_metric`.
+///
+/// Self loops are not counted.
+pub fn degrees(node_count: usize, edges: &[(usize, usize)]) -> Vec<f32> {
+    neighbors(node_count, edges).iter().map(|n| n.len() as f32).collect()
+}
+
+/// Give every node a copy of `style` with its inner radius mapped from its metric through `inner`.
+///
+/// The falloff radius is mapped through `falloff` if there is one, otherwise it keeps its ratio to the inner radius.
+pub fn size_by_metric(style: &Style, metric: &[f32], inner: &Scale, falloff: Option<&Scale>) -> Vec<Style> {
+    metric
+        .iter()
+        .map(|&value| {
+            let inner_radius = inner.map(value);
+            let falloff_radius = match falloff {
+                Some(falloff) => falloff.map(value),
+                None if style.inner_radius > 0.0 => style.falloff_radius * inner_radius / style.inner_radius,
+                None => style.falloff_radius,
+            };
+            Style {
+                inner_radius: inner_radius,
+                falloff_radius: falloff_radius,
+                ..*style
+            }
+        })
+        .collect()
+}
+
+/// Bundle the edges between every pair of clusters into a single ribbon, chord diagram style.
+///
+/// Each ribbon runs between the mean positions of its two clusters and bends halfway towards the mean of every
+/// cluster, so ribbons between opposite sides of the graph arc through the middle. It is `width_per_edge` wide for
+/// every edge it bundles and fades from the categorical color of one cluster to the other. Edges within a cluster
+/// are left out. `clusters` holds the cluster of every node, such as the result of `communities`.
+pub fn ribbons(
+    positions: &[[f32; 2]],
+    clusters: &[usize],
+    edges: &[(usize, usize)],
+    width_per_edge: f32,
+) -> Vec<Ribbon> {
+    let cluster_count = clusters.iter().map(|&c| c + 1).max().unwrap_or(0);
+    let mut sums = vec![([0.0f32; 2], 0usize); cluster_count];
+    for (position, &cluster) in positions.iter().zip(clusters) {
+        let sum = &mut sums[cluster];
+        sum.0[0] += position[0];
+        sum.0[1] += position[1];
+        sum.1 += 1;
+    }
+    let centers = sums
+        .iter()
+        .map(|&(sum, count)| {
+            let count = count.max(1) as f32;
+            [sum[0] / count, sum[1] / count]
+        })
+        .collect::<Vec<_>>();
+    let occupied = sums.iter().filter(|&&(_, count)| count > 0).count().max(1) as f32;
+    let middle = sums
+        .iter()
+        .zip(&centers)
+        .filter(|&(&(_, count), _)| count > 0)
+        .fold([0.0, 0.0], |m, (_, c)| [m[0] + c[0] / occupied, m[1] + c[1] / occupied]);
+
+    let mut bundles = BTreeMap::new();
+    for &(a, b) in edges {
+        let (a, b) = (clusters[a], clusters[b]);
+        if a != b {
+            *bundles.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+
+    bundles
+        .into_iter()
+        .map(|((a, b), count)| {
+            let (from, to) = (centers[a], centers[b]);
+            let width = count as f32 * width_per_edge;
+            let halfway = [0.5 * (from[0] + to[0]), 0.5 * (from[1] + to[1])];
+            Ribbon {
+                position0: from,
+                position1: [0.5 * (halfway[0] + middle[0]), 0.5 * (halfway[1] + middle[1])],
+                position2: to,
+                width0: width,
+                width1: width,
+                color0: palette::categorical(a),
+                color1: palette::categorical(b),
+                fill: 0.3,
+                falloff_radius: 0.25 * width,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::ScaleKind;
+
+    /// Two cliques of four and three nodes that aren't connected to each other, and a node without edges.
+    fn cliques() -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for &(start, end) in &[(0, 4), (4, 7)] {
+            for a in start..end {
+                for b in a + 1..end {
+                    edges.push((a, b));
+                }
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn disconnected_cliques_are_two_communities() {
+        let labels = communities(8, &cliques());
+        // Communities are numbered by size, so the larger clique comes first and the lone node last.
+        assert_eq!(labels, vec![0, 0, 0, 0, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn seeded_communities_are_stable() {
+        let edges = cliques();
+        for seed in 0..8 {
+            let labels = communities_seeded(8, &edges, seed);
+            assert_eq!(labels, communities_seeded(8, &edges, seed));
+            assert_eq!(labels, vec![0, 0, 0, 0, 1, 1, 1, 2]);
+        }
+        assert_eq!(communities(8, &edges), communities_seeded(8, &edges, 0));
+    }
+
+    #[test]
+    fn communities_are_colored_from_the_categorical_palette() {
+        let style = Style::default();
+        let styles = color_by_community_seeded(8, &cliques(), &style, 3);
+        assert_eq!(styles[0], style.with_color(palette::categorical(0)));
+        assert_eq!(styles[5], style.with_color(palette::categorical(1)));
+        assert_eq!(styles[7], style.with_color(palette::categorical(2)));
+        assert_eq!(styles, color_by_community(8, &cliques(), &style));
+    }
+
+    #[test]
+    fn degrees_count_edges_without_self_loops() {
+        assert_eq!(degrees(4, &[(0, 1), (1, 2), (2, 2), (1, 3)]), vec![1.0, 3.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn size_by_metric_keeps_the_falloff_ratio() {
+        let style = Style {
+            inner_radius: 1.0,
+            falloff_radius: 2.0,
+            ..Style::default()
+        };
+        let inner = Scale::new(ScaleKind::Linear, [0.0, 4.0], [1.0, 3.0]);
+        let sized = size_by_metric(&style, &[0.0, 2.0, 4.0], &inner, None);
+        let radii = sized.iter().map(|s| [s.inner_radius, s.falloff_radius]).collect::<Vec<_>>();
+        assert_eq!(radii, vec![[1.0, 2.0], [2.0, 4.0], [3.0, 6.0]]);
+        assert!(sized.iter().all(|s| s.inner_color == style.inner_color));
+
+        let falloff = Scale::new(ScaleKind::Linear, [0.0, 4.0], [5.0, 5.0]);
+        let sized = size_by_metric(&style, &[2.0], &inner, Some(&falloff));
+        assert_eq!([sized[0].inner_radius, sized[0].falloff_radius], [2.0, 5.0]);
+    }
+}
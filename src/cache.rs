@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use glium;
+
+use render2::Node;
+
+/// How much GPU memory a BufferCache is using, for reporting on dashboards and in logs.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    /// The number of bytes held by every cached buffer.
+    pub bytes: usize,
+    /// The number of bytes the cache tries to stay under.
+    pub budget: usize,
+    /// The number of cached buffers.
+    pub buffers: usize,
+    /// The number of buffers evicted since the cache was made.
+    pub evictions: usize,
+}
+
+struct Entry {
+    buffer: glium::VertexBuffer<Node>,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// A BufferCache keeps vertex buffers on the GPU under a memory budget, evicting the least recently used ones.
+///
+/// Long running applications that cycle through many graphs, such as dashboards, can keep the buffers of every graph
+/// in one cache. Buffers are uploaded the first time they are fetched and again if they were evicted since, so
+/// graphs that come back around cost an upload instead of memory the whole time. Invalidate a key when its
+/// vertices change, such as after the damage of a Scene includes it.
+///
+/// The buffer being fetched is never evicted, so a single buffer larger than the budget is still kept.
+pub struct BufferCache<K> {
+    entries: HashMap<K, Entry>,
+    budget: usize,
+    bytes: usize,
+    clock: u64,
+    evictions: usize,
+}
+
+impl<K> BufferCache<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Make an empty cache that tries to stay under `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        BufferCache {
+            entries: HashMap::new(),
+            budget: budget,
+            bytes: 0,
+            clock: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Get the buffer for `key`, uploading `vertices` into a new one if it isn't cached.
+    ///
+    /// Buffers are evicted until the cache is back under budget when a new one is uploaded.
+    pub fn fetch<F>(
+        &mut self,
+        facade: &F,
+        key: K,
+        vertices: &[Node],
+    ) -> Result<&glium::VertexBuffer<Node>, glium::vertex::BufferCreationError>
+    where
+        F: glium::backend::Facade,
+    {
+        self.clock += 1;
+        if !self.entries.contains_key(&key) {
+            let buffer = glium::VertexBuffer::new(facade, vertices)?;
+            let bytes = mem::size_of_val(vertices);
+            trace!("uploading {} bytes into the buffer cache", bytes);
+            self.bytes += bytes;
+            self.entries.insert(
+                key.clone(),
+                Entry {
+                    buffer: buffer,
+                    bytes: bytes,
+                    last_used: self.clock,
+                },
+            );
+            self.evict(Some(&key));
+        }
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.clock;
+        Ok(&entry.buffer)
+    }
+
+    /// Whether the buffer for `key` is on the GPU.
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Drop the buffer for `key` so it is uploaded again the next time it is fetched.
+    pub fn invalidate(&mut self, key: &K) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    /// Drop every buffer.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    /// Change the budget, evicting buffers right away if the cache is over it.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict(None);
+    }
+
+    /// Get how much memory the cache is using.
+    pub fn usage(&self) -> CacheUsage {
+        CacheUsage {
+            bytes: self.bytes,
+            budget: self.budget,
+            buffers: self.entries.len(),
+            evictions: self.evictions,
+        }
+    }
+
+    /// Evict the least recently used buffers other than `keep` until the cache is under budget.
+    fn evict(&mut self, keep: Option<&K>) {
+        while self.bytes > self.budget {
+            let oldest = self.entries
+                .iter()
+                .filter(|&(key, _)| Some(key) != keep)
+                .min_by_key(|&(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    let entry = self.entries.remove(&key).unwrap();
+                    self.bytes -= entry.bytes;
+                    self.evictions += 1;
+                    debug!("evicted {} bytes from the buffer cache", entry.bytes);
+                }
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use render2::Node;
+use style::Style;
+
+/// A node with a double precision world position, for data that spans huge ranges or sits far from the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorldNode {
+    pub position: [f64; 2],
+    pub style: Style,
+}
+
+/// A 2d camera that looks at a point in double precision world space.
+///
+/// Positions are made relative to the camera in double precision before they are cast to `f32`,
+/// so precision is always highest where the camera is looking and deep zooms far from the origin don't jitter.
+/// Use `modelview` and `projection` with the relative nodes when rendering.
+///
+/// A camera can be saved as text with `to_string` and restored with `parse`, and can also be serialized with serde
+/// if the `serialize` feature is enabled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Camera2 {
+    /// The world position at the center of the view.
+    pub center: [f64; 2],
+    /// The scale from world units to view units, where the view is two units tall.
+    pub zoom: f64,
+}
+
+impl Default for Camera2 {
+    fn default() -> Camera2 {
+        Camera2 {
+            center: [0.0, 0.0],
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera2 {
+    /// Make a camera looking at `center` with a given zoom.
+    pub fn new(center: [f64; 2], zoom: f64) -> Self {
+        Camera2 { center: center, zoom: zoom }
+    }
+
+    /// Get a world position relative to the camera center.
+    pub fn relative(&self, position: [f64; 2]) -> [f32; 2] {
+        [
+            (position[0] - self.center[0]) as f32,
+            (position[1] - self.center[1]) as f32,
+        ]
+    }
+
+    /// Get a node ready for rendering from a node with a world position.
+    pub fn relative_node(&self, node: &WorldNode) -> Node {
+        node.style.node2(self.relative(node.position))
+    }
+
+    /// Get nodes ready for rendering from nodes with world positions.
+    pub fn relative_nodes(&self, nodes: &[WorldNode]) -> Vec<Node> {
+        nodes.iter().map(|n| self.relative_node(n)).collect()
+    }
+
+    /// The modelview matrix to render relative nodes with, which only applies the zoom since
+    /// the translation was already done in double precision.
+    pub fn modelview(&self) -> [[f32; 3]; 3] {
+        let zoom = self.zoom as f32;
+        [[zoom, 0.0, 0.0], [0.0, zoom, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    /// The projection matrix that corrects for the aspect ratio of a target of the given size.
+    pub fn projection(&self, width: u32, height: u32) -> [[f32; 3]; 3] {
+        let hscale = height as f32 / width as f32;
+        [[hscale, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    /// Convert a pixel position on a target of the given size (from the top left) into world space.
+    pub fn to_world(&self, x: f64, y: f64, width: u32, height: u32) -> [f64; 2] {
+        let (width, height) = (f64::from(width), f64::from(height));
+        let ndc = [2.0 * x / width - 1.0, 1.0 - 2.0 * y / height];
+        let hscale = height / width;
+        [
+            self.center[0] + ndc[0] / (hscale * self.zoom),
+            self.center[1] + ndc[1] / self.zoom,
+        ]
+    }
+
+    /// Get the world space rectangle (minimum and maximum corners) seen on a target of the given size.
+    pub fn visible_bounds(&self, width: u32, height: u32) -> ([f64; 2], [f64; 2]) {
+        let min = self.to_world(0.0, f64::from(height), width, height);
+        let max = self.to_world(f64::from(width), 0.0, width, height);
+        (min, max)
+    }
+}
+
+impl fmt::Display for Camera2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.center[0], self.center[1], self.zoom)
+    }
+}
+
+/// The error returned when text is not a valid camera.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseCameraError;
+
+impl fmt::Display for ParseCameraError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected a camera as \"x y zoom\"")
+    }
+}
+
+impl Error for ParseCameraError {}
+
+impl FromStr for Camera2 {
+    type Err = ParseCameraError;
+
+    fn from_str(s: &str) -> Result<Camera2, ParseCameraError> {
+        let mut parts = s.split_whitespace().map(|p| p.parse::<f64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(x)), Some(Ok(y)), Some(Ok(zoom)), None) => Ok(Camera2::new([x, y], zoom)),
+            _ => Err(ParseCameraError),
+        }
+    }
+}
+
+/// A list of named viewpoints like "overview" or "cluster A" that can be saved and restored across sessions.
+///
+/// Bookmarks keep the order they were added in. They are saved as one line per bookmark with the name and camera
+/// separated by a tab.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Bookmarks {
+    bookmarks: Vec<(String, Camera2)>,
+}
+
+impl Bookmarks {
+    /// Make an empty list of bookmarks.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a bookmark, replacing any existing bookmark with the same name.
+    ///
+    /// Tabs and line breaks in the name are replaced by spaces so the bookmarks can always be saved.
+    pub fn insert<S>(&mut self, name: S, camera: Camera2)
+    where
+        S: Into<String>,
+    {
+        let name = name.into().replace(&['\t', '\n', '\r'][..], " ");
+        match self.bookmarks.iter_mut().find(|b| b.0 == name) {
+            Some(bookmark) => bookmark.1 = camera,
+            None => self.bookmarks.push((name, camera)),
+        }
+    }
+
+    /// Get the camera of a bookmark.
+    pub fn get(&self, name: &str) -> Option<Camera2> {
+        self.bookmarks.iter().find(|b| b.0 == name).map(|b| b.1)
+    }
+
+    /// Remove a bookmark, returning its camera if it existed.
+    pub fn remove(&mut self, name: &str) -> Option<Camera2> {
+        let index = self.bookmarks.iter().position(|b| b.0 == name)?;
+        Some(self.bookmarks.remove(index).1)
+    }
+
+    /// Iterate over the names and cameras of every bookmark in the order they were added.
+    pub fn iter<'b>(&'b self) -> Box<dyn Iterator<Item = (&'b str, Camera2)> + 'b> {
+        Box::new(self.bookmarks.iter().map(|b| (b.0.as_str(), b.1)))
+    }
+
+    /// Write the bookmarks as text.
+    pub fn save<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for &(ref name, camera) in &self.bookmarks {
+            writeln!(writer, "{}\t{}", name, camera)?;
+        }
+        Ok(())
+    }
+
+    /// Read bookmarks written by `save`.
+    pub fn load<R>(reader: R) -> io::Result<Bookmarks>
+    where
+        R: BufRead,
+    {
+        let mut bookmarks = Bookmarks::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, '\t');
+            let name = parts.next().unwrap_or("");
+            let camera = parts
+                .next()
+                .ok_or(ParseCameraError)
+                .and_then(str::parse)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            bookmarks.insert(name, camera);
+        }
+        Ok(bookmarks)
+    }
+
+    /// Save the bookmarks to a file.
+    pub fn save_file<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Load bookmarks from a file written by `save_file`.
+    pub fn load_file<P>(path: P) -> io::Result<Bookmarks>
+    where
+        P: AsRef<Path>,
+    {
+        Bookmarks::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// The curvature of the zoom out and back in during a flight, as recommended by van Wijk and Nuij.
+const RHO: f64 = ::std::f64::consts::SQRT_2;
+
+/// A smooth, eased flight from one camera to another, made with `Camera2::fly_to`.
+///
+/// The path follows "Smooth and efficient zooming and panning" by van Wijk and Nuij, so long jumps zoom out,
+/// pan, and zoom back in, keeping the apparent speed constant instead of whizzing across a zoomed in view.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraFlight {
+    from: Camera2,
+    to: Camera2,
+    duration: f64,
+    elapsed: f64,
+    distance: f64,
+    r0: f64,
+    length: f64,
+}
+
+impl Camera2 {
+    /// Start a flight from this camera that ends looking at `target` with `zoom` after `duration` seconds.
+    pub fn fly_to(&self, target: [f64; 2], zoom: f64, duration: f64) -> CameraFlight {
+        let to = Camera2::new(target, zoom);
+        // The path is computed in terms of the width of the view in world units.
+        let w0 = 1.0 / self.zoom;
+        let w1 = 1.0 / zoom;
+        let dx = target[0] - self.center[0];
+        let dy = target[1] - self.center[1];
+        let distance = (dx * dx + dy * dy).sqrt();
+        let rho2 = RHO * RHO;
+        let (r0, length) = if distance <= 1e-12 * w0.max(w1) {
+            (0.0, (w1 / w0).ln().abs() / RHO)
+        } else {
+            let b = |w: f64, sign: f64| {
+                (w1 * w1 - w0 * w0 + sign * rho2 * rho2 * distance * distance) / (2.0 * w * rho2 * distance)
+            };
+            let r = |b: f64| (-b + (b * b + 1.0).sqrt()).ln();
+            let r0 = r(b(w0, 1.0));
+            let r1 = r(b(w1, -1.0));
+            (r0, (r1 - r0) / RHO)
+        };
+        CameraFlight {
+            from: *self,
+            to: to,
+            duration: duration,
+            elapsed: 0.0,
+            distance: distance,
+            r0: r0,
+            length: length,
+        }
+    }
+}
+
+impl CameraFlight {
+    /// Get the camera `time` seconds into the flight.
+    pub fn at(&self, time: f64) -> Camera2 {
+        if self.duration <= 0.0 || time >= self.duration {
+            return self.to;
+        }
+        let t = (time / self.duration).max(0.0);
+        // Ease in and out so the flight starts and stops smoothly.
+        let t = t * t * (3.0 - 2.0 * t);
+        let s = t * self.length;
+        let w0 = 1.0 / self.from.zoom;
+        let (travel, width) = if self.distance <= 1e-12 * w0.max(1.0 / self.to.zoom) {
+            let sign = if self.to.zoom < self.from.zoom { 1.0 } else { -1.0 };
+            (t, w0 * (sign * RHO * s).exp())
+        } else {
+            let rho2 = RHO * RHO;
+            let u = w0 / rho2 * (self.r0.cosh() * (RHO * s + self.r0).tanh() - self.r0.sinh());
+            (u / self.distance, w0 * self.r0.cosh() / (RHO * s + self.r0).cosh())
+        };
+        Camera2 {
+            center: [
+                self.from.center[0] + (self.to.center[0] - self.from.center[0]) * travel,
+                self.from.center[1] + (self.to.center[1] - self.from.center[1]) * travel,
+            ],
+            zoom: 1.0 / width,
+        }
+    }
+
+    /// Move the flight forward by `delta` seconds and get the camera for the new frame.
+    pub fn advance(&mut self, delta: f64) -> Camera2 {
+        self.elapsed += delta;
+        self.at(self.elapsed)
+    }
+
+    /// Whether the flight has reached its destination.
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Get the camera the flight ends at.
+    pub fn destination(&self) -> Camera2 {
+        self.to
+    }
+}
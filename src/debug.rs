@@ -0,0 +1,87 @@
+use glium;
+use glium::uniforms::{UniformValue, Uniforms};
+
+/// What the renderers draw, for diagnosing the geometry emitted by the geometry shaders.
+///
+/// Every node and edge is drawn as triangles that cover its whole glow, so an oversized falloff radius can quietly
+/// fill the screen many times over. The debug views make that visible.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DebugView {
+    /// Draw normally.
+    Off,
+    /// Draw normally and then outline every emitted triangle in green.
+    Wireframe,
+    /// Replace the glow with a heatmap of how many triangles cover each pixel.
+    ///
+    /// Every triangle adds a sixteenth of full red (and less green and blue), so pixels covered sixteen times are
+    /// bright red and heavier overdraw goes towards white. Clear the target to black before drawing to read it.
+    Overdraw,
+}
+
+impl Default for DebugView {
+    fn default() -> DebugView {
+        DebugView::Off
+    }
+}
+
+impl DebugView {
+    /// Get the `debug_view` uniform and the draw parameters of each pass needed to draw in this view.
+    pub(crate) fn passes<'a>(self, params: &glium::DrawParameters<'a>) -> Vec<(i32, glium::DrawParameters<'a>)> {
+        match self {
+            DebugView::Off => vec![(0, params.clone())],
+            DebugView::Wireframe => vec![
+                (0, params.clone()),
+                (
+                    1,
+                    glium::DrawParameters {
+                        polygon_mode: glium::PolygonMode::Line,
+                        ..params.clone()
+                    },
+                ),
+            ],
+            DebugView::Overdraw => {
+                let add = glium::BlendingFunction::Addition {
+                    source: glium::LinearBlendingFactor::One,
+                    destination: glium::LinearBlendingFactor::One,
+                };
+                vec![
+                    (
+                        2,
+                        glium::DrawParameters {
+                            blend: glium::Blend {
+                                color: add,
+                                alpha: add,
+                                constant_value: (0.0, 0.0, 0.0, 0.0),
+                            },
+                            // Count every triangle, even the ones that would be hidden.
+                            depth: Default::default(),
+                            ..params.clone()
+                        },
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+/// Uniforms with the `debug_view` uniform added.
+pub(crate) struct DebugUniforms<'u, U>
+where
+    U: 'u,
+{
+    pub uniforms: &'u U,
+    pub view: i32,
+}
+
+impl<'u, U> Uniforms for DebugUniforms<'u, U>
+where
+    U: Uniforms,
+{
+    fn visit_values<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&str, UniformValue<'a>),
+    {
+        self.uniforms.visit_values(&mut f);
+        f("debug_view", UniformValue::SignedInt(self.view));
+    }
+}
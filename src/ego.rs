@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+
+use glium::{self, Surface};
+
+use render2::Renderer;
+use scene::{Element, Scene};
+
+/// Find the nodes of a Scene within `hops` edges of `focus`, including the focus itself.
+///
+/// Edges are followed in both directions.
+pub fn neighborhood(scene: &Scene, focus: usize, hops: usize) -> HashSet<usize> {
+    let mut neighbors: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (_, edge) in scene.edges() {
+        neighbors.entry(edge.from).or_default().push(edge.to);
+        neighbors.entry(edge.to).or_default().push(edge.from);
+    }
+    let mut reached = HashSet::new();
+    if scene.node(focus).is_none() {
+        return reached;
+    }
+    reached.insert(focus);
+    let mut frontier = vec![focus];
+    for _ in 0..hops {
+        let mut next = Vec::new();
+        for node in frontier {
+            for &neighbor in neighbors.get(&node).map(|n| n.as_slice()).unwrap_or(&[]) {
+                if reached.insert(neighbor) {
+                    next.push(neighbor);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+    reached
+}
+
+/// An EgoHighlight dims everything in a Scene except the neighborhood of a focus node, the most common way of
+/// exploring a graph one node at a time.
+///
+/// Call `focus` when the focus changes, such as when a node is clicked, and `advance` every frame. Elements fade
+/// between being lit and dimmed over `duration` seconds. An edge is lit when both of its nodes are. When the focus
+/// changes during a fade, the new fade starts from wherever the old one had got to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EgoHighlight {
+    /// How many edges away from the focus nodes stay lit.
+    pub hops: usize,
+    /// How much of their opacity dimmed elements keep, from `0.0` to `1.0`.
+    pub dim: f32,
+    /// How long elements take to fade in seconds.
+    pub duration: f64,
+    from: Option<HashSet<usize>>,
+    to: Option<HashSet<usize>>,
+    /// The opacity of the nodes and edges that were still fading when the focus last changed, which their new fade
+    /// starts from instead of their level in `from`.
+    from_nodes: HashMap<usize, f32>,
+    from_edges: HashMap<(usize, usize), f32>,
+    elapsed: f64,
+}
+
+impl Default for EgoHighlight {
+    fn default() -> EgoHighlight {
+        EgoHighlight::new(1, 0.15, 0.25)
+    }
+}
+
+impl EgoHighlight {
+    /// Make an EgoHighlight with nothing focused.
+    pub fn new(hops: usize, dim: f32, duration: f64) -> Self {
+        EgoHighlight {
+            hops: hops,
+            dim: dim,
+            duration: duration,
+            from: None,
+            to: None,
+            from_nodes: HashMap::new(),
+            from_edges: HashMap::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Focus on the neighborhood of a node, or light everything again with `None`.
+    ///
+    /// The neighborhood is found when this is called, so call it again if the edges of the scene change.
+    pub fn focus(&mut self, scene: &Scene, node: Option<usize>) {
+        let mut from_nodes = HashMap::new();
+        let mut from_edges = HashMap::new();
+        if !self.finished() {
+            for (i, _) in scene.nodes() {
+                let opacity = self.node_opacity(i);
+                if opacity != self.level(&self.to, |set| set.contains(&i)) {
+                    from_nodes.insert(i, opacity);
+                }
+            }
+            for (_, edge) in scene.edges() {
+                let opacity = self.edge_opacity(edge.from, edge.to);
+                if opacity != self.level(&self.to, |set| set.contains(&edge.from) && set.contains(&edge.to)) {
+                    from_edges.insert((edge.from, edge.to), opacity);
+                }
+            }
+        }
+        let to = node.map(|node| neighborhood(scene, node, self.hops));
+        self.from = ::std::mem::replace(&mut self.to, to);
+        self.from_nodes = from_nodes;
+        self.from_edges = from_edges;
+        self.elapsed = 0.0;
+    }
+
+    /// Get the set of nodes that are lit, or `None` if nothing is focused.
+    pub fn lit(&self) -> Option<&HashSet<usize>> {
+        self.to.as_ref()
+    }
+
+    /// Move the fade forward by `delta` seconds.
+    pub fn advance(&mut self, delta: f64) {
+        self.elapsed += delta;
+    }
+
+    /// Whether the elements have finished fading.
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Get the factor the opacity of a node is scaled by at this point in the fade.
+    pub fn node_opacity(&self, node: usize) -> f32 {
+        self.opacity(self.from_nodes.get(&node), |set| set.contains(&node))
+    }
+
+    /// Get the factor the opacity of an edge between two nodes is scaled by at this point in the fade.
+    pub fn edge_opacity(&self, from: usize, to: usize) -> f32 {
+        self.opacity(self.from_edges.get(&(from, to)), |set| set.contains(&from) && set.contains(&to))
+    }
+
+    /// Clear the target to the Theme background and draw the scene like `Scene::render` with the highlight applied.
+    pub fn render<'a, D, S>(
+        &self,
+        renderer: &Renderer<'a, D>,
+        scene: &mut Scene,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        scene.render_with_opacity(renderer, target, modelview, projection, |element| match element {
+            Element::Node(node) => self.node_opacity(node),
+            Element::Edge { from, to, .. } => self.edge_opacity(from, to),
+        });
+    }
+
+    /// Get the opacity an element settles at when `set` is lit, where `contains` tells if the element is in a set.
+    fn level<F>(&self, set: &Option<HashSet<usize>>, contains: F) -> f32
+    where
+        F: Fn(&HashSet<usize>) -> bool,
+    {
+        match *set {
+            Some(ref set) if !contains(set) => self.dim,
+            _ => 1.0,
+        }
+    }
+
+    fn opacity<F>(&self, start: Option<&f32>, contains: F) -> f32
+    where
+        F: Fn(&HashSet<usize>) -> bool,
+    {
+        let t = if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).min(1.0) as f32
+        };
+        // Ease in and out so the fade starts and stops smoothly.
+        let t = t * t * (3.0 - 2.0 * t);
+        let from = start.cloned().unwrap_or_else(|| self.level(&self.from, &contains));
+        from * (1.0 - t) + self.level(&self.to, &contains) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scene::{SceneEdge, SceneNode};
+
+    /// A path of three nodes.
+    fn path() -> Scene {
+        let mut scene = Scene::default();
+        for _ in 0..3 {
+            scene.add_node(SceneNode::default());
+        }
+        for &(from, to) in &[(0, 1), (1, 2)] {
+            scene.add_edge(SceneEdge {
+                from: from,
+                to: to,
+                ..Default::default()
+            });
+        }
+        scene
+    }
+
+    #[test]
+    fn neighborhood_follows_edges_both_ways() {
+        let scene = path();
+        let reached = |focus, hops| {
+            let mut nodes = neighborhood(&scene, focus, hops).into_iter().collect::<Vec<_>>();
+            nodes.sort();
+            nodes
+        };
+        assert_eq!(reached(2, 0), vec![2]);
+        assert_eq!(reached(2, 1), vec![1, 2]);
+        assert_eq!(reached(1, 1), vec![0, 1, 2]);
+        assert!(neighborhood(&scene, 5, 1).is_empty());
+    }
+
+    #[test]
+    fn fades_between_lit_and_dimmed() {
+        let scene = path();
+        let mut ego = EgoHighlight::new(0, 0.2, 1.0);
+        ego.focus(&scene, Some(0));
+        assert_eq!(ego.node_opacity(2), 1.0);
+        ego.advance(0.5);
+        assert!((ego.node_opacity(2) - 0.6).abs() < 1e-6);
+        ego.advance(0.5);
+        assert!(ego.finished());
+        assert_eq!(ego.node_opacity(0), 1.0);
+        assert_eq!(ego.node_opacity(2), 0.2);
+        assert_eq!(ego.edge_opacity(0, 1), 0.2);
+    }
+
+    #[test]
+    fn refocusing_mid_fade_starts_from_the_current_opacity() {
+        let scene = path();
+        let mut ego = EgoHighlight::new(0, 0.2, 1.0);
+        ego.focus(&scene, Some(0));
+        ego.advance(0.5);
+        let node = ego.node_opacity(2);
+        let edge = ego.edge_opacity(1, 2);
+        ego.focus(&scene, Some(2));
+        assert_eq!(ego.node_opacity(2), node);
+        assert_eq!(ego.edge_opacity(1, 2), edge);
+        assert_eq!(ego.node_opacity(0), 1.0);
+        assert!((ego.node_opacity(1) - 0.6).abs() < 1e-6);
+        ego.advance(1.0);
+        assert_eq!(ego.node_opacity(2), 1.0);
+        assert_eq!(ego.node_opacity(0), 0.2);
+    }
+}
@@ -0,0 +1,225 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use glium::Rect;
+use glium::texture::Texture2d;
+
+/// A frame read back from the GPU as 32 bit floats, for exporting the glow field to other tools.
+///
+/// Render into a floating point texture (such as one made with `Texture2d::empty_with_format` and
+/// `UncompressedFloatFormat::F32F32F32F32`, or the average of a `post::Accumulator`) so that overlapping glow can add
+/// up past one without being clamped, then read it back with `read`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FloatImage {
+    pub width: u32,
+    pub height: u32,
+    /// The RGBA pixels row by row from the top.
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl FloatImage {
+    /// Read a texture back from the GPU.
+    pub fn read(texture: &Texture2d) -> Self {
+        // `Texture2d::read` only reads 8 bit pixels, so the float pixels are read from the image directly.
+        let (width, height) = texture.dimensions();
+        let rect = Rect {
+            left: 0,
+            bottom: 0,
+            width: width,
+            height: height,
+        };
+        let rows: Vec<Vec<(f32, f32, f32, f32)>> = texture
+            .main_level()
+            .first_layer()
+            .into_image(None)
+            .unwrap()
+            .raw_read(&rect);
+        // OpenGL rows start at the bottom.
+        let pixels = rows.iter()
+            .rev()
+            .flat_map(|row| row.iter().map(|&(r, g, b, a)| [r, g, b, a]))
+            .collect();
+        FloatImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// Get the intensity of every pixel row by row from the top, which is the Rec. 709 luminance of its color.
+    pub fn intensity(&self) -> Vec<f32> {
+        self.pixels
+            .iter()
+            .map(|p| 0.2126 * p[0] + 0.7152 * p[1] + 0.0722 * p[2])
+            .collect()
+    }
+
+    /// Write the intensity as a raw grid of floats, such as for use as a heightfield.
+    ///
+    /// The width and height are written first as little endian 32 bit unsigned integers, followed by the intensity
+    /// of every pixel row by row from the top as little endian 32 bit floats.
+    pub fn write_raw<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        for value in self.intensity() {
+            writer.write_all(&value.to_bits().to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Write the image as an uncompressed OpenEXR file with 32 bit float R, G, B, and A channels.
+    pub fn write_exr<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut header = Vec::new();
+        // The magic number and version 2 with no flags, which is a single part scanline image.
+        header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        // Channels must be sorted by name. Each has a type of FLOAT, is not linear, and isn't subsampled.
+        let mut channels = Vec::new();
+        for name in b"ABGR" {
+            channels.extend_from_slice(&[*name, 0]);
+            channels.extend_from_slice(&2i32.to_le_bytes());
+            channels.extend_from_slice(&[0, 0, 0, 0]);
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+        attribute(&mut header, "channels", "chlist", &channels);
+        attribute(&mut header, "compression", "compression", &[0]);
+        let mut window = Vec::new();
+        for &v in &[0, 0, self.width as i32 - 1, self.height as i32 - 1] {
+            window.extend_from_slice(&v.to_le_bytes());
+        }
+        attribute(&mut header, "dataWindow", "box2i", &window);
+        attribute(&mut header, "displayWindow", "box2i", &window);
+        attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+        attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_bits().to_le_bytes());
+        attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+        attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_bits().to_le_bytes());
+        header.push(0);
+        writer.write_all(&header)?;
+
+        // Every scanline is its own block, which starts with its row and the size of its pixel data.
+        let width = self.width as usize;
+        let line = 4 * 4 * width;
+        let first = header.len() + 8 * self.height as usize;
+        for y in 0..self.height as usize {
+            writer.write_all(&((first + y * (8 + line)) as u64).to_le_bytes())?;
+        }
+        for (y, row) in self.pixels.chunks(width.max(1)).enumerate() {
+            writer.write_all(&(y as i32).to_le_bytes())?;
+            writer.write_all(&(line as i32).to_le_bytes())?;
+            for &channel in &[3, 2, 1, 0] {
+                for pixel in row {
+                    writer.write_all(&pixel[channel].to_bits().to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the intensity to a file with `write_raw`.
+    pub fn save_raw<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_raw(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Save the image to a file with `write_exr`.
+    pub fn save_exr<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_exr(&mut writer)?;
+        writer.flush()
+    }
+}
+
+/// Append an attribute of the OpenEXR header, which is its name, its type, and the size of its value before it.
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> FloatImage {
+        FloatImage {
+            width: 2,
+            height: 3,
+            pixels: (0..6).map(|i| [i as f32, 0.5, 2.0, 1.0]).collect(),
+        }
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    }
+
+    fn f32_at(bytes: &[u8], at: usize) -> f32 {
+        f32::from_bits(u32_at(bytes, at))
+    }
+
+    #[test]
+    fn raw_is_the_size_then_the_intensity() {
+        let image = image();
+        let mut raw = Vec::new();
+        image.write_raw(&mut raw).unwrap();
+        assert_eq!(raw.len(), 8 + 4 * 6);
+        assert_eq!((u32_at(&raw, 0), u32_at(&raw, 4)), (2, 3));
+        for (i, intensity) in image.intensity().into_iter().enumerate() {
+            assert_eq!(f32_at(&raw, 8 + 4 * i), intensity);
+        }
+    }
+
+    #[test]
+    fn exr_header_and_scanlines_line_up() {
+        let image = image();
+        let mut exr = Vec::new();
+        image.write_exr(&mut exr).unwrap();
+        assert_eq!(&exr[..4], &[0x76, 0x2f, 0x31, 0x01]);
+
+        // Walk the attributes to find where the header ends.
+        let mut at = 8;
+        let mut names = Vec::new();
+        while exr[at] != 0 {
+            let name_end = at + exr[at..].iter().position(|&b| b == 0).unwrap();
+            names.push(String::from_utf8(exr[at..name_end].to_vec()).unwrap());
+            let kind_end = name_end + 1 + exr[name_end + 1..].iter().position(|&b| b == 0).unwrap();
+            at = kind_end + 5 + u32_at(&exr, kind_end + 1) as usize;
+        }
+        let header = at + 1;
+        assert_eq!(names[0], "channels");
+        assert!(names.contains(&"dataWindow".to_string()));
+
+        // Every offset points at a block with its row, the size of its data, and then A, B, G, and R for the row.
+        let line = 4 * 4 * 2;
+        assert_eq!(exr.len(), header + 8 * 3 + 3 * (8 + line));
+        for y in 0..3 {
+            let offset = u32_at(&exr, header + 8 * y) as usize;
+            assert_eq!(u32_at(&exr, offset), y as u32);
+            assert_eq!(u32_at(&exr, offset + 4) as usize, line);
+            let data = offset + 8;
+            assert_eq!(f32_at(&exr, data), 1.0);
+            assert_eq!(f32_at(&exr, data + 8), 2.0);
+            assert_eq!(f32_at(&exr, data + 16), 0.5);
+            assert_eq!(f32_at(&exr, data + 24), (2 * y) as f32);
+            assert_eq!(f32_at(&exr, data + 28), (2 * y + 1) as f32);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+
+use glium::{self, Surface};
+
+use render2::{Node, Renderer};
+use routing::Rect;
+use style::Style;
+
+/// The latitude in degrees beyond which Web Mercator is cut off, which makes the projected world square.
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// The deepest slippy map zoom level, beyond which tiles can't be numbered with `u32` and are smaller than the
+/// precision of world space.
+pub const MAX_TILE_ZOOM: u32 = 30;
+
+/// The smallest number of degrees between the lines of a `Graticule`, which keeps it to about fifty thousand lines.
+pub const MIN_GRATICULE_STEP: f64 = 0.01;
+
+/// A way of flattening latitude and longitude onto the plane, for graphs of places such as airports or routers.
+///
+/// Both projections put longitudes from -180 to 180 degrees at x from -1 to 1, with north up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MapProjection {
+    /// The projection of most web maps, which keeps shapes but enlarges areas towards the poles.
+    ///
+    /// Latitudes are clamped to `MAX_MERCATOR_LATITUDE`, which is projected to y of 1.
+    WebMercator,
+    /// Latitude and longitude used directly as y and x, so latitudes from -90 to 90 degrees go from -0.5 to 0.5.
+    Equirectangular,
+}
+
+impl MapProjection {
+    /// Project a latitude and longitude in degrees into world space.
+    pub fn project(self, latitude: f64, longitude: f64) -> [f32; 2] {
+        let x = longitude / 180.0;
+        let y = match self {
+            MapProjection::WebMercator => {
+                let latitude = latitude
+                    .max(-MAX_MERCATOR_LATITUDE)
+                    .min(MAX_MERCATOR_LATITUDE)
+                    .to_radians();
+                (PI / 4.0 + latitude / 2.0).tan().ln() / PI
+            }
+            MapProjection::Equirectangular => latitude / 180.0,
+        };
+        [x as f32, y as f32]
+    }
+
+    /// Find the latitude and longitude in degrees of a position in world space, such as the one under the cursor.
+    pub fn unproject(self, position: [f32; 2]) -> (f64, f64) {
+        let [x, y] = [f64::from(position[0]), f64::from(position[1])];
+        let latitude = match self {
+            MapProjection::WebMercator => (2.0 * (y * PI).exp().atan() - PI / 2.0).to_degrees(),
+            MapProjection::Equirectangular => y * 180.0,
+        };
+        (latitude, x * 180.0)
+    }
+
+    /// Project the latitudes and longitudes (in that order) of many places at once.
+    pub fn project_all(self, coordinates: &[(f64, f64)]) -> Vec<[f32; 2]> {
+        coordinates
+            .iter()
+            .map(|&(latitude, longitude)| self.project(latitude, longitude))
+            .collect()
+    }
+
+    /// The range of latitudes that can be projected.
+    fn latitudes(self) -> (f64, f64) {
+        match self {
+            MapProjection::WebMercator => (-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE),
+            MapProjection::Equirectangular => (-90.0, 90.0),
+        }
+    }
+}
+
+/// Get the Web Mercator rectangle covered by the slippy map tile (as used by OpenStreetMap) at `x` and `y`.
+///
+/// Tiles are numbered from the top left and there are `2^zoom` of them across at each zoom level. The rectangle can
+/// be passed to `Renderer::render_images` along with the image of the tile. Zoom levels beyond `MAX_TILE_ZOOM` are
+/// treated as `MAX_TILE_ZOOM`.
+pub fn tile_bounds(zoom: u32, x: u32, y: u32) -> Rect {
+    let size = 2.0 / (1u64 << zoom.min(MAX_TILE_ZOOM)) as f32;
+    let (x, y) = (x as f32, y as f32);
+    Rect {
+        min: [x * size - 1.0, 1.0 - (y + 1.0) * size],
+        max: [(x + 1.0) * size - 1.0, 1.0 - y * size],
+    }
+}
+
+/// Get the column and row of every slippy map tile at `zoom` that overlaps `view`, which is in Web Mercator world
+/// space, so the caller knows which tiles to fetch and draw. Zoom levels beyond `MAX_TILE_ZOOM` are treated as
+/// `MAX_TILE_ZOOM`.
+pub fn visible_tiles(view: &Rect, zoom: u32) -> Vec<(u32, u32)> {
+    let count = 1u64 << zoom.min(MAX_TILE_ZOOM);
+    let size = 2.0 / count as f32;
+    let column = |x: f32| ((x + 1.0) / size).floor().max(0.0).min((count - 1) as f32) as u32;
+    let row = |y: f32| ((1.0 - y) / size).floor().max(0.0).min((count - 1) as f32) as u32;
+    if view.max[0] < -1.0 || view.min[0] > 1.0 || view.max[1] < -1.0 || view.min[1] > 1.0 {
+        return Vec::new();
+    }
+    let mut tiles = Vec::new();
+    for y in row(view.max[1])..=row(view.min[1]) {
+        for x in column(view.min[0])..=column(view.max[0]) {
+            tiles.push((x, y));
+        }
+    }
+    tiles
+}
+
+/// The lines of latitude and longitude every `step` degrees, drawn under a graph to show where it is on the globe.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Graticule {
+    pub projection: MapProjection,
+    /// The number of degrees between neighboring lines, which is at least `MIN_GRATICULE_STEP`.
+    pub step: f64,
+    /// The style the lines are drawn with, which usually should be faint.
+    pub style: Style,
+}
+
+impl Graticule {
+    /// Get every line of the graticule as a flat edge (two nodes each), meridians first and then parallels.
+    ///
+    /// Both projections keep meridians and parallels straight, so each line is a single edge.
+    pub fn edges(&self) -> Vec<Node> {
+        let mut edges = Vec::new();
+        if self.step.is_nan() {
+            return edges;
+        }
+        let step = self.step.max(MIN_GRATICULE_STEP);
+        let (south, north) = self.projection.latitudes();
+        let mut longitude = -180.0;
+        while longitude <= 180.0 {
+            edges.push(self.style.node2(self.projection.project(south, longitude)));
+            edges.push(self.style.node2(self.projection.project(north, longitude)));
+            longitude += step;
+        }
+        // Parallels are spread out from the equator, so it always has one.
+        let mut latitude = (south / step).trunc() * step;
+        while latitude <= north {
+            edges.push(self.style.node2(self.projection.project(latitude, -180.0)));
+            edges.push(self.style.node2(self.projection.project(latitude, 180.0)));
+            latitude += step;
+        }
+        edges
+    }
+
+    /// Draw the graticule, which should be done before the graph that goes over it.
+    pub fn render<'a, D, S>(
+        &self,
+        renderer: &Renderer<'a, D>,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        let edges = self.edges();
+        if !edges.is_empty() {
+            renderer.render_edges_flat(target, modelview, projection, &edges);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn projections_round_trip() {
+        for &projection in &[MapProjection::WebMercator, MapProjection::Equirectangular] {
+            for &(latitude, longitude) in &[(0.0, 0.0), (51.5, -0.13), (-33.9, 151.2), (60.0, 180.0)] {
+                let (back_latitude, back_longitude) = projection.unproject(projection.project(latitude, longitude));
+                assert!(close(back_latitude, latitude), "{:?} {}", projection, latitude);
+                assert!(close(back_longitude, longitude), "{:?} {}", projection, longitude);
+            }
+        }
+    }
+
+    #[test]
+    fn web_mercator_is_square() {
+        let projection = MapProjection::WebMercator;
+        assert_eq!(projection.project(90.0, -180.0), [-1.0, 1.0]);
+        assert_eq!(projection.project(-90.0, 180.0), [1.0, -1.0]);
+        assert_eq!(MapProjection::Equirectangular.project(90.0, 90.0), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn tiles_are_numbered_from_the_top_left() {
+        assert_eq!(tile_bounds(0, 0, 0), Rect { min: [-1.0, -1.0], max: [1.0, 1.0] });
+        assert_eq!(tile_bounds(1, 0, 0), Rect { min: [-1.0, 0.0], max: [0.0, 1.0] });
+        assert_eq!(tile_bounds(2, 3, 1), Rect { min: [0.5, 0.0], max: [1.0, 0.5] });
+    }
+
+    #[test]
+    fn visible_tiles_cover_the_view() {
+        let world = Rect { min: [-1.0, -1.0], max: [1.0, 1.0] };
+        assert_eq!(visible_tiles(&world, 1), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let corner = Rect { min: [0.6, -0.9], max: [0.9, -0.6] };
+        assert_eq!(visible_tiles(&corner, 2), vec![(3, 3)]);
+        let outside = Rect { min: [2.0, 2.0], max: [3.0, 3.0] };
+        assert!(visible_tiles(&outside, 2).is_empty());
+    }
+
+    #[test]
+    fn deep_zoom_levels_are_clamped() {
+        assert_eq!(tile_bounds(64, 0, 0), tile_bounds(MAX_TILE_ZOOM, 0, 0));
+        let view = Rect { min: [0.0, 0.0], max: [0.0, 0.0] };
+        assert_eq!(visible_tiles(&view, 100), visible_tiles(&view, MAX_TILE_ZOOM));
+    }
+
+    #[test]
+    fn graticule_steps_are_limited() {
+        let graticule = |step| Graticule {
+            projection: MapProjection::Equirectangular,
+            step: step,
+            style: Style::default(),
+        };
+        // Meridians at -180, -90, 0, 90, and 180 and parallels at -90, 0, and 90.
+        assert_eq!(graticule(90.0).edges().len(), 2 * 8);
+        assert_eq!(graticule(0.0).edges().len(), graticule(MIN_GRATICULE_STEP).edges().len());
+        assert!(graticule(::std::f64::NAN).edges().is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use seed::Rng;
+
+/// Make lists of neighbors for every node from an edge list.
+///
+/// Self loops are skipped and edges to nodes past `node_count` panic.
+pub(crate) fn neighbors(node_count: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); node_count];
+    for &(a, b) in edges {
+        if a != b {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+    }
+    neighbors
+}
+
+/// Lay out a graph with the degree-normalized eigenvectors of its Laplacian that have the smallest nonzero eigenvalues.
+///
+/// This is fast and deterministic, which makes it a good starting point for iterative layouts.
+/// Positions are scaled to fit in `[-1, 1]` on both axes. Separate connected components are not pulled apart
+/// and may end up on top of each other, so it works best on connected graphs. This is `spectral_seeded` with a seed
+/// of zero.
+pub fn spectral(node_count: usize, edges: &[(usize, usize)]) -> Vec<[f32; 2]> {
+    spectral_seeded(node_count, edges, 0)
+}
+
+/// Lay out a graph like `spectral`, where `seed` picks the starting vectors of the power iteration.
+///
+/// Different seeds can only mirror the result or change it within the tolerance, and the same seed always gives the
+/// same layout.
+pub fn spectral_seeded(node_count: usize, edges: &[(usize, usize)], seed: u64) -> Vec<[f32; 2]> {
+    if node_count == 0 {
+        return Vec::new();
+    }
+    let neighbors = neighbors(node_count, edges);
+    let degrees = neighbors.iter().map(|n| n.len().max(1) as f32).collect::<Vec<_>>();
+
+    // The constant vector is always an eigenvector (with eigenvalue zero) and is projected out.
+    let mut vectors = vec![vec![1.0 / (node_count as f32).sqrt(); node_count]];
+    let mut rng = Rng::new(seed);
+    for _ in 0..2 {
+        // A random starting vector is very unlikely to be orthogonal to the eigenvector we want.
+        let mut u = (0..node_count).map(|_| rng.range(-0.5, 0.5)).collect::<Vec<_>>();
+        d_orthonormalize(&mut u, &vectors, &degrees);
+        for _ in 0..1000 {
+            // Power iteration on (I + D^-1 A) / 2, whose top eigenvectors are the ones we want.
+            let mut next = (0..node_count)
+                .map(|i| {
+                    let sum = neighbors[i].iter().map(|&j| u[j]).sum::<f32>();
+                    0.5 * (u[i] + sum / degrees[i])
+                })
+                .collect::<Vec<_>>();
+            d_orthonormalize(&mut next, &vectors, &degrees);
+            let similarity = u.iter().zip(&next).map(|(a, b)| a * b).sum::<f32>();
+            u = next;
+            if similarity > 1.0 - 1e-6 {
+                break;
+            }
+        }
+        vectors.push(u);
+    }
+
+    let scale = |v: &[f32]| 1.0 / v.iter().fold(::std::f32::EPSILON, |m, x| m.max(x.abs()));
+    let (sx, sy) = (scale(&vectors[1]), scale(&vectors[2]));
+    (0..node_count)
+        .map(|i| [vectors[1][i] * sx, vectors[2][i] * sy])
+        .collect()
+}
+
+/// Make `u` D-orthogonal to every vector in `basis` and give it unit length.
+fn d_orthonormalize(u: &mut [f32], basis: &[Vec<f32>], degrees: &[f32]) {
+    for b in basis {
+        let num = u.iter().zip(b).zip(degrees).map(|((u, b), d)| u * d * b).sum::<f32>();
+        let den = b.iter().zip(degrees).map(|(b, d)| b * d * b).sum::<f32>();
+        if den > 0.0 {
+            for (u, b) in u.iter_mut().zip(b) {
+                *u -= num / den * b;
+            }
+        }
+    }
+    let norm = u.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in u.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct Visit {
+    distance: f32,
+    node: usize,
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Visit) -> Ordering {
+        // Reversed so the BinaryHeap pops the closest node first.
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Visit) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the length of the shortest path between every pair of nodes, stored row by row.
+///
+/// Unreachable pairs are infinite.
+pub(crate) fn shortest_paths(node_count: usize, edges: &[(usize, usize, f32)]) -> Vec<f32> {
+    let mut adjacent = vec![Vec::new(); node_count];
+    for &(a, b, length) in edges {
+        if a != b && length > 0.0 {
+            adjacent[a].push((b, length));
+            adjacent[b].push((a, length));
+        }
+    }
+    let mut distances = vec![::std::f32::INFINITY; node_count * node_count];
+    let mut heap = BinaryHeap::new();
+    for source in 0..node_count {
+        let row = &mut distances[source * node_count..(source + 1) * node_count];
+        row[source] = 0.0;
+        heap.push(Visit {
+            distance: 0.0,
+            node: source,
+        });
+        while let Some(Visit { distance, node }) = heap.pop() {
+            if distance > row[node] {
+                continue;
+            }
+            for &(next, length) in &adjacent[node] {
+                let distance = distance + length;
+                if distance < row[next] {
+                    row[next] = distance;
+                    heap.push(Visit {
+                        distance: distance,
+                        node: next,
+                    });
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// Lay out a graph so that the distance between every pair of nodes matches the length of the shortest path between
+/// them as closely as possible, where `edges` give the target length of every edge.
+///
+/// This minimizes stress by majorization, starting from the spectral layout, which preserves metric data much better
+/// than force-directed layouts. Positions are in the same units as the edge lengths. Edges that are not longer than
+/// zero are ignored and disconnected components are placed a typical distance apart. This is
+/// `stress_majorization_seeded` with a seed of zero.
+pub fn stress_majorization(node_count: usize, edges: &[(usize, usize, f32)], iterations: usize) -> Vec<[f32; 2]> {
+    stress_majorization_seeded(node_count, edges, iterations, 0)
+}
+
+/// Lay out a graph like `stress_majorization`, where `seed` is passed on to `spectral_seeded` and used to nudge apart
+/// nodes that start in the same place, so the same seed always gives the same layout.
+pub fn stress_majorization_seeded(
+    node_count: usize,
+    edges: &[(usize, usize, f32)],
+    iterations: usize,
+    seed: u64,
+) -> Vec<[f32; 2]> {
+    let n = node_count;
+    if n < 2 {
+        return vec![[0.0, 0.0]; n];
+    }
+    let mut targets = shortest_paths(n, edges);
+    let finite = targets.iter().cloned().filter(|d| d.is_finite() && *d > 0.0).collect::<Vec<_>>();
+    let mean = if finite.is_empty() {
+        1.0
+    } else {
+        finite.iter().sum::<f32>() / finite.len() as f32
+    };
+    let longest = finite.iter().cloned().fold(mean, f32::max);
+    for d in &mut targets {
+        if !d.is_finite() {
+            *d = longest + mean;
+        }
+    }
+
+    let unweighted = edges.iter().map(|&(a, b, _)| (a, b)).collect::<Vec<_>>();
+    let mut positions = spectral_seeded(n, &unweighted, seed);
+    // Scale the initial layout so that its typical distance matches the typical target distance.
+    let mut layout_mean = 0.0;
+    for i in 0..n {
+        for j in 0..n {
+            layout_mean += distance(positions[i], positions[j]);
+        }
+    }
+    layout_mean /= (n * (n - 1)) as f32;
+    let scale = if layout_mean > 0.0 { mean / layout_mean } else { 1.0 };
+    // A different stream than the one `spectral` used, so the nudges don't line up with its starting vectors.
+    let mut rng = Rng::new(!seed);
+    for p in &mut positions {
+        p[0] *= scale;
+        p[1] *= scale;
+        // Nodes that start on top of each other can never be separated, so nudge them apart.
+        p[0] += 1e-3 * mean * rng.next_f32();
+        p[1] += 1e-3 * mean * rng.next_f32();
+    }
+
+    let mut last_stress = ::std::f32::INFINITY;
+    for _ in 0..iterations {
+        for i in 0..n {
+            let mut sum = [0.0, 0.0];
+            let mut weights = 0.0;
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let target = targets[i * n + j];
+                if target <= 0.0 {
+                    continue;
+                }
+                let w = 1.0 / (target * target);
+                let d = distance(positions[i], positions[j]).max(::std::f32::EPSILON);
+                sum[0] += w * (positions[j][0] + target * (positions[i][0] - positions[j][0]) / d);
+                sum[1] += w * (positions[j][1] + target * (positions[i][1] - positions[j][1]) / d);
+                weights += w;
+            }
+            if weights > 0.0 {
+                positions[i] = [sum[0] / weights, sum[1] / weights];
+            }
+        }
+
+        let mut stress = 0.0;
+        for i in 0..n {
+            for j in i + 1..n {
+                let target = targets[i * n + j];
+                if target > 0.0 {
+                    let error = distance(positions[i], positions[j]) - target;
+                    stress += error * error / (target * target);
+                }
+            }
+        }
+        if last_stress - stress < 1e-5 * last_stress {
+            break;
+        }
+        last_stress = stress;
+    }
+    positions
+}
+
+pub(crate) fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+/// Parameters of the force-directed relaxation done by `force_directed` and `relax_local`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Forces {
+    /// The length edges settle at when they are not disturbed by other nodes.
+    pub edge_length: f32,
+    pub iterations: usize,
+    /// How many edges away from a changed node a node can be and still move in `relax_local`.
+    pub hops: usize,
+}
+
+impl Default for Forces {
+    fn default() -> Forces {
+        Forces {
+            edge_length: 0.1,
+            iterations: 50,
+            hops: 2,
+        }
+    }
+}
+
+/// Relax every node of a graph with a Fruchterman-Reingold force-directed layout, starting from `positions`.
+///
+/// Seeding `positions` with the `spectral` layout makes this converge in far fewer iterations than a random start.
+/// This is `force_directed_seeded` with a seed of zero.
+pub fn force_directed(positions: &mut [[f32; 2]], edges: &[(usize, usize)], forces: &Forces) {
+    force_directed_seeded(positions, edges, forces, 0);
+}
+
+/// Relax a graph like `force_directed`, where `seed` picks the directions that nodes on top of each other are spread
+/// out in, so the same seed and starting positions always relax to the same layout.
+pub fn force_directed_seeded(positions: &mut [[f32; 2]], edges: &[(usize, usize)], forces: &Forces, seed: u64) {
+    let active = vec![true; positions.len()];
+    relax(positions, edges, &active, forces, seed);
+}
+
+/// Relax only the neighborhood of nodes that changed, keeping every other node pinned where it is.
+///
+/// Nodes within `forces.hops` edges of a node in `changed` are moved by a force-directed layout while the rest of the
+/// graph stays put, so live graphs can be updated without reshuffling everything the user has already seen.
+/// New nodes can be placed anywhere (such as on top of a neighbor) before calling this. This is
+/// `relax_local_seeded` with a seed of zero.
+pub fn relax_local(positions: &mut [[f32; 2]], edges: &[(usize, usize)], changed: &[usize], forces: &Forces) {
+    relax_local_seeded(positions, edges, changed, forces, 0);
+}
+
+/// Relax the neighborhood of changed nodes like `relax_local`, where `seed` is used like in `force_directed_seeded`.
+pub fn relax_local_seeded(
+    positions: &mut [[f32; 2]],
+    edges: &[(usize, usize)],
+    changed: &[usize],
+    forces: &Forces,
+    seed: u64,
+) {
+    let neighbors = neighbors(positions.len(), edges);
+    let mut active = vec![false; positions.len()];
+    let mut frontier = changed.to_vec();
+    for &node in changed {
+        active[node] = true;
+    }
+    for _ in 0..forces.hops {
+        let mut next = Vec::new();
+        for node in frontier {
+            for &neighbor in &neighbors[node] {
+                if !active[neighbor] {
+                    active[neighbor] = true;
+                    next.push(neighbor);
+                }
+            }
+        }
+        frontier = next;
+    }
+    relax(positions, edges, &active, forces, seed);
+}
+
+fn relax(positions: &mut [[f32; 2]], edges: &[(usize, usize)], active: &[bool], forces: &Forces, seed: u64) {
+    let n = positions.len();
+    let k = forces.edge_length;
+    let moving = (0..n).filter(|&i| active[i]).collect::<Vec<_>>();
+    if moving.is_empty() || forces.iterations == 0 {
+        return;
+    }
+
+    // Nodes placed exactly on top of each other have no direction to repel in, so spread them out slightly.
+    let mut rng = Rng::new(seed);
+    for &i in &moving {
+        if (0..n).any(|j| j != i && positions[j] == positions[i]) {
+            let angle = rng.range(0.0, 2.0 * ::std::f32::consts::PI);
+            positions[i][0] += 0.01 * k * angle.cos();
+            positions[i][1] += 0.01 * k * angle.sin();
+        }
+    }
+
+    let cutoff = 3.0 * k;
+    let mut displacement = vec![[0.0f32; 2]; n];
+    for iteration in 0..forces.iterations {
+        // The largest step a node can take cools down linearly.
+        let temperature = 0.1 * k * (1.0 - iteration as f32 / forces.iterations as f32);
+        for d in &mut displacement {
+            *d = [0.0, 0.0];
+        }
+
+        for &i in &moving {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let delta = [positions[i][0] - positions[j][0], positions[i][1] - positions[j][1]];
+                let d = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt().max(0.01 * k);
+                if d < cutoff {
+                    let force = k * k / d;
+                    displacement[i][0] += delta[0] / d * force;
+                    displacement[i][1] += delta[1] / d * force;
+                }
+            }
+        }
+
+        for &(a, b) in edges {
+            if a == b || !(active[a] || active[b]) {
+                continue;
+            }
+            let delta = [positions[a][0] - positions[b][0], positions[a][1] - positions[b][1]];
+            let d = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt().max(0.01 * k);
+            let force = d * d / k;
+            let pull = [delta[0] / d * force, delta[1] / d * force];
+            displacement[a][0] -= pull[0];
+            displacement[a][1] -= pull[1];
+            displacement[b][0] += pull[0];
+            displacement[b][1] += pull[1];
+        }
+
+        for &i in &moving {
+            let [dx, dy] = displacement[i];
+            let length = (dx * dx + dy * dy).sqrt();
+            if length > 0.0 {
+                let step = length.min(temperature) / length;
+                positions[i][0] += dx * step;
+                positions[i][1] += dy * step;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(n: usize) -> Vec<(usize, usize)> {
+        (1..n).map(|i| (i - 1, i)).collect()
+    }
+
+    #[test]
+    fn neighbors_skip_self_loops() {
+        let neighbors = neighbors(3, &[(0, 1), (1, 1), (1, 2)]);
+        assert_eq!(neighbors, vec![vec![1], vec![0, 2], vec![1]]);
+    }
+
+    #[test]
+    fn spectral_orders_a_path() {
+        assert!(spectral(0, &[]).is_empty());
+        let positions = spectral(8, &path(8));
+        assert!(positions.iter().all(|p| p.iter().all(|v| v.abs() <= 1.0 + 1e-5)));
+        // The first axis is the Fiedler vector, which is monotonic along a path.
+        let increasing = positions.windows(2).all(|w| w[0][0] < w[1][0]);
+        let decreasing = positions.windows(2).all(|w| w[0][0] > w[1][0]);
+        assert!(increasing || decreasing);
+    }
+
+    #[test]
+    fn seeded_layouts_are_reproducible() {
+        let edges = path(6);
+        assert_eq!(spectral_seeded(6, &edges, 5), spectral_seeded(6, &edges, 5));
+        assert_eq!(spectral(6, &edges), spectral_seeded(6, &edges, 0));
+        let weighted = edges.iter().map(|&(a, b)| (a, b, 1.0)).collect::<Vec<_>>();
+        assert_eq!(
+            stress_majorization_seeded(6, &weighted, 50, 9),
+            stress_majorization_seeded(6, &weighted, 50, 9)
+        );
+    }
+
+    #[test]
+    fn shortest_paths_take_the_lighter_route() {
+        let distances = shortest_paths(4, &[(0, 1, 1.0), (1, 2, 1.0), (0, 2, 3.0), (3, 3, 1.0)]);
+        assert_eq!(distances[2], 2.0);
+        assert_eq!(distances[2 * 4], 2.0);
+        assert_eq!(distances[4 + 1], 0.0);
+        assert!(distances[3].is_infinite());
+    }
+
+    #[test]
+    fn stress_majorization_matches_path_lengths() {
+        let edges = [(0, 1, 1.0), (1, 2, 1.0), (2, 3, 2.0)];
+        let positions = stress_majorization(4, &edges, 200);
+        assert!((distance(positions[0], positions[1]) - 1.0).abs() < 0.05);
+        assert!((distance(positions[2], positions[3]) - 2.0).abs() < 0.05);
+        assert!((distance(positions[0], positions[3]) - 4.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn force_directed_separates_stacked_nodes() {
+        let mut positions = vec![[0.0, 0.0]; 3];
+        force_directed(&mut positions, &path(3), &Forces::default());
+        for i in 0..3 {
+            for j in i + 1..3 {
+                assert!(distance(positions[i], positions[j]) > 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn relax_local_pins_distant_nodes() {
+        let mut positions = (0..5).map(|i| [i as f32 * 0.1, 0.0]).collect::<Vec<_>>();
+        positions[0] = positions[1];
+        let before = positions.clone();
+        let forces = Forces {
+            hops: 1,
+            ..Default::default()
+        };
+        relax_local(&mut positions, &path(5), &[0], &forces);
+        assert_ne!(positions[0], before[0]);
+        assert_eq!(&positions[2..], &before[2..]);
+    }
+}
@@ -1,6 +1,58 @@
+// Field names are always spelled out in struct literals in this crate.
+#![allow(clippy::redundant_field_names)]
+
 extern crate cgmath;
 #[macro_use]
 extern crate glium;
+#[macro_use]
+extern crate log;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde_derive")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "toml_styles")]
+extern crate toml;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
+pub mod analysis;
+pub mod cache;
+pub mod camera;
+pub mod debug;
+pub mod ego;
+pub mod export;
+pub mod geo;
+pub mod layout;
+pub mod load;
+pub mod palette;
+#[cfg(feature = "toml_styles")]
+pub mod persist;
+pub mod pick;
+// The vertex types of these modules are made with glium's `implement_vertex!`, which uses the deprecated
+// `mem::uninitialized`.
+#[allow(deprecated)]
+pub mod post;
+#[cfg(feature = "rayon")]
+pub mod prepare;
+mod program;
+#[allow(deprecated)]
 pub mod render2;
+#[allow(deprecated)]
 pub mod render3;
+pub mod routing;
+pub mod scale;
+pub mod scene;
+pub mod seed;
+pub mod spline;
+pub mod style;
+pub mod temporal;
+pub mod theme;
+pub mod tiles;
+mod uniforms;
+pub mod validate;
+pub mod view;
+pub mod visibility;
+pub mod watch;
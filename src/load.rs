@@ -0,0 +1,594 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use serde_json;
+
+use scene::{Scene, SceneEdge, SceneNode};
+
+/// A node in a SceneDescription.
+///
+/// Nodes without a position are placed on a circle when the description is applied to a Scene.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize))]
+pub struct NodeDescription {
+    pub id: String,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub position: Option<[f32; 2]>,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub style: Option<String>,
+}
+
+/// An edge in a SceneDescription, referring to its nodes by id.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize))]
+pub struct EdgeDescription {
+    pub from: String,
+    pub to: String,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub style: Option<String>,
+}
+
+/// A graph loaded from a file that can be turned into a Scene.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize))]
+pub struct SceneDescription {
+    #[cfg_attr(feature = "json", serde(default))]
+    pub nodes: Vec<NodeDescription>,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub edges: Vec<EdgeDescription>,
+}
+
+/// The file formats a SceneDescription can be loaded from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SceneFormat {
+    /// A subset of the Graphviz DOT language.
+    ///
+    /// Node `pos` attributes (`pos="x,y"`) set positions and `style` or `class` attributes set the style name.
+    /// `node [...]` and `edge [...]` statements set the style of the nodes and edges after them, and the statements
+    /// of subgraphs are added like any others.
+    Dot,
+    /// `{"nodes": [{"id", "position", "style"}], "edges": [{"from", "to", "style"}]}`.
+    ///
+    /// Requires the `json` feature.
+    Json,
+}
+
+impl SceneFormat {
+    /// Guess the format of a file from its extension, defaulting to DOT.
+    pub fn from_path<P>(path: P) -> SceneFormat
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("json") => SceneFormat::Json,
+            _ => SceneFormat::Dot,
+        }
+    }
+}
+
+/// The ways loading a SceneDescription can fail.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The DOT source was malformed on a given line.
+    Dot { line: usize, message: String },
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The `json` feature is needed to load JSON.
+    JsonUnsupported,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref e) => write!(f, "failed to read scene: {}", e),
+            LoadError::Dot { line, ref message } => write!(f, "invalid DOT on line {}: {}", line, message),
+            #[cfg(feature = "json")]
+            LoadError::Json(ref e) => write!(f, "invalid JSON scene: {}", e),
+            LoadError::JsonUnsupported => write!(f, "loading JSON scenes requires the json feature"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> LoadError {
+        LoadError::Json(e)
+    }
+}
+
+impl SceneDescription {
+    /// Load a SceneDescription from a file, guessing the format from the extension.
+    pub fn load<P>(path: P) -> Result<SceneDescription, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut source = String::new();
+        File::open(path.as_ref())?.read_to_string(&mut source)?;
+        SceneDescription::parse(&source, SceneFormat::from_path(path))
+    }
+
+    /// Parse a SceneDescription from source text in a given format.
+    pub fn parse(source: &str, format: SceneFormat) -> Result<SceneDescription, LoadError> {
+        match format {
+            SceneFormat::Dot => parse_dot(source),
+            SceneFormat::Json => parse_json(source),
+        }
+    }
+
+    /// Get the position of every node, placing nodes that have no position evenly on the unit circle.
+    pub fn positions(&self) -> Vec<[f32; 2]> {
+        let count = self.nodes.len() as f32;
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                n.position.unwrap_or_else(|| {
+                    let angle = 2.0 * ::std::f32::consts::PI * i as f32 / count;
+                    [angle.cos(), angle.sin()]
+                })
+            })
+            .collect()
+    }
+
+    /// Add the described nodes and edges to a Scene, returning the scene index of every node in order.
+    ///
+    /// Edges that refer to unknown node ids are skipped. Everything is added in one step of the scene's history.
+    pub fn add_to(&self, scene: &mut Scene) -> Vec<usize> {
+        scene.begin_transaction();
+        let indices = self.nodes
+            .iter()
+            .zip(self.positions())
+            .map(|(n, position)| {
+                scene.add_node(SceneNode {
+                    position: position,
+                    style: n.style.clone(),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+        for edge in &self.edges {
+            let from = self.nodes.iter().position(|n| n.id == edge.from);
+            let to = self.nodes.iter().position(|n| n.id == edge.to);
+            if let (Some(from), Some(to)) = (from, to) {
+                scene.add_edge(SceneEdge {
+                    from: indices[from],
+                    to: indices[to],
+                    style: edge.style.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        scene.commit_transaction();
+        indices
+    }
+}
+
+#[cfg(feature = "json")]
+fn parse_json(source: &str) -> Result<SceneDescription, LoadError> {
+    Ok(serde_json::from_str(source)?)
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_json(_: &str) -> Result<SceneDescription, LoadError> {
+    Err(LoadError::JsonUnsupported)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Id(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, LoadError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => {
+                while chars.peek().map(|&c| c != '\n').unwrap_or(false) {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().map(|&c| c != '\n').unwrap_or(false) {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        }
+                        None => {
+                            return Err(LoadError::Dot {
+                                line: line,
+                                message: "unterminated comment".to_owned(),
+                            })
+                        }
+                    }
+                }
+            }
+            '{' => tokens.push((line, Token::Symbol("{"))),
+            '}' => tokens.push((line, Token::Symbol("}"))),
+            '[' => tokens.push((line, Token::Symbol("["))),
+            ']' => tokens.push((line, Token::Symbol("]"))),
+            '=' => tokens.push((line, Token::Symbol("="))),
+            ';' => tokens.push((line, Token::Symbol(";"))),
+            ',' => tokens.push((line, Token::Symbol(","))),
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((line, Token::Symbol("->")));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                tokens.push((line, Token::Symbol("--")));
+            }
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => id.push('"'),
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                id.push('\\');
+                                id.push(c);
+                            }
+                            None => {}
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        }
+                        None => {
+                            return Err(LoadError::Dot {
+                                line: start,
+                                message: "unterminated string".to_owned(),
+                            })
+                        }
+                    }
+                }
+                tokens.push((start, Token::Id(id)));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        id.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((line, Token::Id(id)));
+            }
+            c => {
+                return Err(LoadError::Dot {
+                    line: line,
+                    message: format!("unexpected character '{}'", c),
+                })
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_dot(source: &str) -> Result<SceneDescription, LoadError> {
+    let tokens = tokenize(source)?;
+    let mut description = SceneDescription::default();
+    let last_line = tokens.last().map(|t| t.0).unwrap_or(1);
+
+    // Skip the `strict? (graph|digraph) name? {` header.
+    let mut pos = match tokens.iter().position(|t| t.1 == Token::Symbol("{")) {
+        Some(open) => open + 1,
+        None => return Err(dot_error(last_line, "expected '{'")),
+    };
+    parse_statements(&tokens, &mut pos, &mut description, &Defaults::default())?;
+    Ok(description)
+}
+
+/// The styles given by `node [style=...]` and `edge [style=...]` statements, which last until the end of the
+/// enclosing graph or subgraph.
+#[derive(Clone, Debug, Default)]
+struct Defaults {
+    node: Option<String>,
+    edge: Option<String>,
+}
+
+fn dot_error(line: usize, message: &str) -> LoadError {
+    LoadError::Dot {
+        line: line,
+        message: message.to_owned(),
+    }
+}
+
+/// Parse statements up to and including the `}` that closes the graph or subgraph whose body starts at `pos`.
+///
+/// Subgraphs only group statements, so their nodes and edges are added to the description like any other.
+fn parse_statements(
+    tokens: &[(usize, Token)],
+    pos: &mut usize,
+    description: &mut SceneDescription,
+    defaults: &Defaults,
+) -> Result<(), LoadError> {
+    let mut defaults = defaults.clone();
+    let last_line = tokens.last().map(|t| t.0).unwrap_or(1);
+
+    loop {
+        let (line, token) = match tokens.get(*pos) {
+            Some(t) => t.clone(),
+            None => return Err(dot_error(last_line, "expected '}'")),
+        };
+        *pos += 1;
+        let id = match token {
+            Token::Symbol("}") => return Ok(()),
+            Token::Symbol(";") => continue,
+            // An anonymous subgraph.
+            Token::Symbol("{") => {
+                parse_statements(tokens, pos, description, &defaults)?;
+                continue;
+            }
+            Token::Id(id) => id,
+            Token::Symbol(s) => return Err(dot_error(line, &format!("unexpected '{}'", s))),
+        };
+
+        // Graph-wide attribute statements like `rankdir=LR` are ignored.
+        if tokens.get(*pos).map(|t| &t.1) == Some(&Token::Symbol("=")) {
+            *pos += 2;
+            continue;
+        }
+
+        // Keywords are case insensitive.
+        let keyword = id.to_lowercase();
+        if keyword == "subgraph" {
+            if let Some(&(_, Token::Id(_))) = tokens.get(*pos) {
+                *pos += 1;
+            }
+            match tokens.get(*pos) {
+                Some(&(_, Token::Symbol("{"))) => {
+                    *pos += 1;
+                    parse_statements(tokens, pos, description, &defaults)?;
+                }
+                _ => return Err(dot_error(line, "expected '{' after subgraph")),
+            }
+            continue;
+        }
+        if keyword == "node" || keyword == "edge" || keyword == "graph" {
+            let attributes = parse_attributes(tokens, pos)?;
+            let style = style_attribute(&attributes);
+            match keyword.as_str() {
+                "node" => defaults.node = style.or_else(|| defaults.node.take()),
+                "edge" => defaults.edge = style.or_else(|| defaults.edge.take()),
+                // Graph attributes don't affect how anything is drawn.
+                _ => {}
+            }
+            continue;
+        }
+
+        // Collect a chain of edge operands `a -> b -> c`.
+        let mut chain = vec![id];
+        loop {
+            match tokens.get(*pos) {
+                Some(&(line, Token::Symbol(op))) if op == "->" || op == "--" => {
+                    match tokens.get(*pos + 1) {
+                        Some(&(_, Token::Id(ref id))) => chain.push(id.clone()),
+                        _ => return Err(dot_error(line, "expected node id after edge operator")),
+                    }
+                    *pos += 2;
+                }
+                _ => break,
+            }
+        }
+
+        let attributes = parse_attributes(tokens, pos)?;
+        let style = style_attribute(&attributes);
+
+        if chain.len() == 1 {
+            let position = match attributes.iter().find(|(k, _)| k == "pos") {
+                Some((_, value)) => {
+                    Some(parse_pos(value).ok_or_else(|| dot_error(line, "invalid pos attribute"))?)
+                }
+                None => None,
+            };
+            let id = chain.pop().unwrap();
+            // Repeated node statements update the existing node.
+            match description.nodes.iter().position(|n| n.id == id) {
+                Some(i) => {
+                    let node = &mut description.nodes[i];
+                    node.position = position.or(node.position);
+                    node.style = style.or_else(|| node.style.take());
+                }
+                None => description.nodes.push(NodeDescription {
+                    id: id,
+                    position: position,
+                    style: style.or_else(|| defaults.node.clone()),
+                }),
+            }
+        } else {
+            for id in &chain {
+                if !description.nodes.iter().any(|n| &n.id == id) {
+                    description.nodes.push(NodeDescription {
+                        id: id.clone(),
+                        position: None,
+                        style: defaults.node.clone(),
+                    });
+                }
+            }
+            let style = style.or_else(|| defaults.edge.clone());
+            for pair in chain.windows(2) {
+                description.edges.push(EdgeDescription {
+                    from: pair[0].clone(),
+                    to: pair[1].clone(),
+                    style: style.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Parse an optional `[key=value, ...]` attribute list at `pos`.
+fn parse_attributes(tokens: &[(usize, Token)], pos: &mut usize) -> Result<Vec<(String, String)>, LoadError> {
+    let mut attributes = Vec::new();
+    if tokens.get(*pos).map(|t| &t.1) != Some(&Token::Symbol("[")) {
+        return Ok(attributes);
+    }
+    let last_line = tokens.last().map(|t| t.0).unwrap_or(1);
+    *pos += 1;
+    loop {
+        match tokens.get(*pos).cloned() {
+            Some((_, Token::Symbol("]"))) => {
+                *pos += 1;
+                return Ok(attributes);
+            }
+            Some((_, Token::Symbol(","))) | Some((_, Token::Symbol(";"))) => *pos += 1,
+            Some((line, Token::Id(key))) => match (tokens.get(*pos + 1), tokens.get(*pos + 2)) {
+                (Some(&(_, Token::Symbol("="))), Some(&(_, Token::Id(ref value)))) => {
+                    attributes.push((key, value.clone()));
+                    *pos += 3;
+                }
+                _ => return Err(dot_error(line, "expected attribute value")),
+            },
+            Some((line, _)) => return Err(dot_error(line, "malformed attribute list")),
+            None => return Err(dot_error(last_line, "expected ']'")),
+        }
+    }
+}
+
+/// Get the style named by a `style` or `class` attribute.
+fn style_attribute(attributes: &[(String, String)]) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(k, _)| k == "style" || k == "class")
+        .map(|(_, v)| v.clone())
+}
+
+fn parse_pos(value: &str) -> Option<[f32; 2]> {
+    let mut parts = value.trim_end_matches('!').split(',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    Some([x, y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(description: &SceneDescription) -> Vec<&str> {
+        description.nodes.iter().map(|n| n.id.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_nodes_and_edge_chains() {
+        let description = parse_dot(
+            r#"digraph g {
+                rankdir=LR
+                a [pos="1,2", style=hub];
+                a -> b -> c [class=strong]
+            }"#,
+        ).unwrap();
+        assert_eq!(ids(&description), vec!["a", "b", "c"]);
+        assert_eq!(description.nodes[0].position, Some([1.0, 2.0]));
+        assert_eq!(description.nodes[0].style, Some("hub".to_owned()));
+        assert_eq!(description.edges.len(), 2);
+        assert_eq!(description.edges[1].from, "b");
+        assert_eq!(description.edges[1].style, Some("strong".to_owned()));
+    }
+
+    #[test]
+    fn default_attribute_statements_are_not_nodes() {
+        let description = parse_dot(
+            "graph {
+                graph [splines=true]
+                node [style=small]
+                edge [style=thin]
+                a -- b
+                c [style=big]
+                NODE [style=other]
+                d
+            }",
+        ).unwrap();
+        assert_eq!(ids(&description), vec!["a", "b", "c", "d"]);
+        let styles = description.nodes.iter().map(|n| n.style.as_ref().unwrap().as_str()).collect::<Vec<_>>();
+        assert_eq!(styles, vec!["small", "small", "big", "other"]);
+        assert_eq!(description.edges[0].style, Some("thin".to_owned()));
+    }
+
+    #[test]
+    fn subgraph_bodies_are_parsed() {
+        let description = parse_dot(
+            "digraph {
+                subgraph cluster_0 {
+                    node [style=inner]
+                    a -> b
+                }
+                subgraph { c }
+                { d }
+                a -> e
+            }",
+        ).unwrap();
+        assert_eq!(ids(&description), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(description.nodes[1].style, Some("inner".to_owned()));
+        // Defaults end with the subgraph they were given in.
+        assert_eq!(description.nodes[4].style, None);
+        assert_eq!(description.edges.len(), 2);
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        match parse_dot("graph {\n a -- \n}") {
+            Err(LoadError::Dot { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a DOT error, got {:?}", other),
+        }
+        assert!(parse_dot("graph { subgraph x a }").is_err());
+        assert!(parse_dot("graph { a").is_err());
+    }
+
+    #[test]
+    fn unpositioned_nodes_go_on_the_unit_circle() {
+        let description = parse_dot("graph { a b [pos=\"3,4\"] }").unwrap();
+        let positions = description.positions();
+        assert_eq!(positions[0], [1.0, 0.0]);
+        assert_eq!(positions[1], [3.0, 4.0]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn parses_json() {
+        let description = SceneDescription::parse(
+            r#"{"nodes": [{"id": "a", "position": [1, 2]}, {"id": "b", "style": "hub"}],
+                "edges": [{"from": "a", "to": "b"}]}"#,
+            SceneFormat::Json,
+        ).unwrap();
+        assert_eq!(ids(&description), vec!["a", "b"]);
+        assert_eq!(description.nodes[0].position, Some([1.0, 2.0]));
+        assert_eq!(description.edges[0].to, "b");
+        assert!(SceneDescription::parse("{\"nodes\": 3}", SceneFormat::Json).is_err());
+    }
+}
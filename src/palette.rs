@@ -0,0 +1,56 @@
+use seed::Rng;
+
+/// Ten colors that are easy to tell apart, for coloring categories such as communities or node types.
+///
+/// These are the Tableau 10 colors.
+pub const CATEGORICAL: [[f32; 4]; 10] = [
+    [0.122, 0.467, 0.706, 1.0],
+    [1.0, 0.498, 0.055, 1.0],
+    [0.173, 0.627, 0.173, 1.0],
+    [0.839, 0.153, 0.157, 1.0],
+    [0.580, 0.404, 0.741, 1.0],
+    [0.549, 0.337, 0.294, 1.0],
+    [0.890, 0.467, 0.761, 1.0],
+    [0.498, 0.498, 0.498, 1.0],
+    [0.737, 0.741, 0.133, 1.0],
+    [0.090, 0.745, 0.812, 1.0],
+];
+
+/// Get the categorical color of a category, wrapping around once the palette runs out.
+pub fn categorical(category: usize) -> [f32; 4] {
+    CATEGORICAL[category % CATEGORICAL.len()]
+}
+
+/// Generate `count` distinct colors for when there are more categories than `CATEGORICAL` holds.
+///
+/// Hues are spaced by the golden angle from a starting hue picked by `seed`, so neighboring categories stay far apart
+/// on the color wheel and the same seed always gives the same colors.
+pub fn generate(count: usize, seed: u64) -> Vec<[f32; 4]> {
+    let start = Rng::new(seed).next_f32();
+    (0..count)
+        .map(|i| {
+            let hue = (start + i as f32 * 0.381_966).fract();
+            // Alternate the lightness a little so colors with close hues can still be told apart.
+            let value = if i % 2 == 0 { 0.85 } else { 0.65 };
+            let [r, g, b] = hsv(hue, 0.65, value);
+            [r, g, b, 1.0]
+        })
+        .collect()
+}
+
+/// Convert a color from hue, saturation and value in `[0, 1]` to RGB.
+fn hsv(hue: f32, saturation: f32, value: f32) -> [f32; 3] {
+    let h = hue * 6.0;
+    let c = value * saturation;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let [r, g, b] = match h as u32 {
+        0 => [c, x, 0.0],
+        1 => [x, c, 0.0],
+        2 => [0.0, c, x],
+        3 => [0.0, x, c],
+        4 => [x, 0.0, c],
+        _ => [c, 0.0, x],
+    };
+    let m = value - c;
+    [r + m, g + m, b + m]
+}
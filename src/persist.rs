@@ -0,0 +1,176 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use toml;
+
+use style::StyleSheet;
+use theme::Theme;
+
+/// An error loading or saving a StyleSheet or Theme as TOML.
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    /// The TOML was malformed or had unknown keys, which the message names along with where they are.
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PersistError::Io(ref e) => write!(f, "failed to access style file: {}", e),
+            PersistError::Parse(ref e) => write!(f, "invalid style TOML: {}", e),
+            PersistError::Serialize(ref e) => write!(f, "failed to write style TOML: {}", e),
+        }
+    }
+}
+
+impl Error for PersistError {}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> PersistError {
+        PersistError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for PersistError {
+    fn from(e: toml::de::Error) -> PersistError {
+        PersistError::Parse(e)
+    }
+}
+
+impl From<toml::ser::Error> for PersistError {
+    fn from(e: toml::ser::Error) -> PersistError {
+        PersistError::Serialize(e)
+    }
+}
+
+fn read<P>(path: P) -> Result<String, PersistError>
+where
+    P: AsRef<Path>,
+{
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+    Ok(source)
+}
+
+fn write<P>(path: P, source: &str) -> Result<(), PersistError>
+where
+    P: AsRef<Path>,
+{
+    File::create(path)?.write_all(source.as_bytes())?;
+    Ok(())
+}
+
+impl StyleSheet {
+    /// Parse a StyleSheet from TOML with a `[fallback]` table and a `[styles.<name>]` table per style.
+    ///
+    /// Missing fields take their default values and unknown fields are errors.
+    pub fn from_toml(source: &str) -> Result<StyleSheet, PersistError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Write the StyleSheet as TOML that `from_toml` reads.
+    pub fn to_toml(&self) -> Result<String, PersistError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Load a StyleSheet from a TOML file.
+    pub fn load_toml<P>(path: P) -> Result<StyleSheet, PersistError>
+    where
+        P: AsRef<Path>,
+    {
+        StyleSheet::from_toml(&read(path)?)
+    }
+
+    /// Save the StyleSheet to a TOML file.
+    pub fn save_toml<P>(&self, path: P) -> Result<(), PersistError>
+    where
+        P: AsRef<Path>,
+    {
+        write(path, &self.to_toml()?)
+    }
+}
+
+impl Theme {
+    /// Parse a Theme from TOML with a `background` color and `[node]`, `[edge]` and `[selection]` style tables.
+    ///
+    /// Missing fields take their default values and unknown fields are errors.
+    pub fn from_toml(source: &str) -> Result<Theme, PersistError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Write the Theme as TOML that `from_toml` reads.
+    pub fn to_toml(&self) -> Result<String, PersistError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Load a Theme from a TOML file.
+    pub fn load_toml<P>(path: P) -> Result<Theme, PersistError>
+    where
+        P: AsRef<Path>,
+    {
+        Theme::from_toml(&read(path)?)
+    }
+
+    /// Save the Theme to a TOML file.
+    pub fn save_toml<P>(&self, path: P) -> Result<(), PersistError>
+    where
+        P: AsRef<Path>,
+    {
+        write(path, &self.to_toml()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use style::Style;
+
+    #[test]
+    fn style_sheets_round_trip() {
+        let mut sheet = StyleSheet::with_fallback(Style::default().with_color([0.5, 0.25, 1.0, 1.0]));
+        let server = Style {
+            inner_radius: 0.5,
+            ..Style::default()
+        };
+        sheet.insert("server", server);
+        let parsed = StyleSheet::from_toml(&sheet.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed.get("server"), Some(&server));
+        assert_eq!(parsed.fallback(), sheet.fallback());
+        assert_eq!(parsed.iter().count(), 1);
+    }
+
+    #[test]
+    fn themes_round_trip() {
+        for theme in &[Theme::dark_neon(), Theme::light(), Theme::high_contrast()] {
+            assert_eq!(&Theme::from_toml(&theme.to_toml().unwrap()).unwrap(), theme);
+        }
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let sheet = StyleSheet::from_toml("[styles.server]\ninner_radius = 0.5\n").unwrap();
+        let server = Style {
+            inner_radius: 0.5,
+            ..Style::default()
+        };
+        assert_eq!(sheet.get("server"), Some(&server));
+        assert_eq!(sheet.fallback(), &Style::default());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        match StyleSheet::from_toml("[styles.server]\ninner_raduis = 0.5\n") {
+            Err(PersistError::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        match Theme::from_toml("foreground = [1.0, 1.0, 1.0, 1.0]\n") {
+            Err(PersistError::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+
+use render2::Node;
+
+/// Find the node whose glow (inner radius plus falloff radius) covers `point` and whose center is closest to it.
+///
+/// `point` is in the same space as the node positions, while the radii are in view space like they are when drawn,
+/// so `modelview` must be the matrix the nodes are drawn with. Returns the index of the node and the distance from
+/// its center, in the space of the node positions.
+pub fn pick_node(nodes: &[Node], modelview: [[f32; 3]; 3], point: [f32; 2]) -> Option<(usize, f32)> {
+    nodes
+        .iter()
+        .enumerate()
+        .filter(|&(_, n)| view_distance(modelview, n.position, point) <= n.inner_radius + n.falloff_radius)
+        .map(|(i, n)| (i, distance(n.position, point)))
+        .fold(None, |best: Option<(usize, f32)>, (i, d)| match best {
+            Some((_, best_d)) if best_d <= d => best,
+            _ => Some((i, d)),
+        })
+}
+
+/// Find the `k` nodes with centers closest to `point`, nearest first, along with the distance to each center.
+///
+/// Unlike `pick_node` this doesn't require the point to be inside a node, which allows snapping to nodes that are
+/// too small to hit and offering alternatives when several nodes overlap.
+pub fn k_nearest(nodes: &[Node], point: [f32; 2], k: usize) -> Vec<(usize, f32)> {
+    let mut nearest = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (i, distance(n.position, point)))
+        .collect::<Vec<_>>();
+    nearest.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    nearest.truncate(k);
+    nearest
+}
+
+/// Find every node with a center within `radius` of `point`, nearest first, along with the distance to each center.
+pub fn within(nodes: &[Node], point: [f32; 2], radius: f32) -> Vec<(usize, f32)> {
+    let mut found = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (i, distance(n.position, point)))
+        .filter(|&(_, d)| d <= radius)
+        .collect::<Vec<_>>();
+    found.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    found
+}
+
+/// The distance between two points after they are transformed by `modelview`, which only depends on its linear part.
+fn view_distance(modelview: [[f32; 3]; 3], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1]];
+    // Matrices are stored column by column.
+    distance(
+        [
+            modelview[0][0] * d[0] + modelview[1][0] * d[1],
+            modelview[0][1] * d[0] + modelview[1][1] * d[1],
+        ],
+        [0.0, 0.0],
+    )
+}
+
+pub(crate) fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    let dx = a[0] - b[0];
+    let dy = a[1] - b[1];
+    (dx * dx + dy * dy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Point2;
+
+    const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    fn node(x: f32, y: f32) -> Node {
+        let mut node = Node::from(Point2::new(x, y));
+        node.inner_radius = 0.01;
+        node.falloff_radius = 0.01;
+        node
+    }
+
+    #[test]
+    fn picks_closest_covering_node() {
+        let nodes = [node(0.0, 0.0), node(0.015, 0.0), node(1.0, 1.0)];
+        assert_eq!(pick_node(&nodes, IDENTITY, [0.01, 0.0]).map(|(i, _)| i), Some(1));
+        assert_eq!(pick_node(&nodes, IDENTITY, [0.5, 0.5]), None);
+    }
+
+    #[test]
+    fn radii_are_in_view_space() {
+        let nodes = [node(0.0, 0.0)];
+        let zoomed_in = [[10.0, 0.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 1.0]];
+        let zoomed_out = [[0.1, 0.0, 0.0], [0.0, 0.1, 0.0], [0.0, 0.0, 1.0]];
+        // The glow reaches 0.02 in view space, which is 0.002 world units when zoomed in and 0.2 when zoomed out.
+        assert!(pick_node(&nodes, zoomed_in, [0.005, 0.0]).is_none());
+        assert!(pick_node(&nodes, zoomed_in, [0.001, 0.0]).is_some());
+        assert!(pick_node(&nodes, zoomed_out, [0.1, 0.0]).is_some());
+        assert!(pick_node(&nodes, zoomed_out, [0.3, 0.0]).is_none());
+    }
+
+    #[test]
+    fn translation_does_not_change_radii() {
+        let nodes = [node(0.0, 0.0)];
+        let panned = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [5.0, -3.0, 1.0]];
+        assert!(pick_node(&nodes, panned, [0.015, 0.0]).is_some());
+        assert!(pick_node(&nodes, panned, [0.025, 0.0]).is_none());
+    }
+
+    #[test]
+    fn nearest_and_within_are_sorted() {
+        let nodes = [node(0.3, 0.0), node(0.1, 0.0), node(0.2, 0.0)];
+        let nearest = k_nearest(&nodes, [0.0, 0.0], 2);
+        assert_eq!(nearest.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![1, 2]);
+        let found = within(&nodes, [0.0, 0.0], 0.25);
+        assert_eq!(found.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
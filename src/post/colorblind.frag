@@ -0,0 +1,21 @@
+#version 150
+
+in vec2 uv;
+out vec4 color;
+uniform sampler2D frame;
+uniform mat3 simulation;
+
+vec3 to_linear(vec3 c) {
+    return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+}
+
+vec3 to_srgb(vec3 c) {
+    return mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, step(0.0031308, c));
+}
+
+void main() {
+    vec4 source = texture(frame, uv);
+    // The simulation is only valid on linear light.
+    vec3 simulated = clamp(simulation * to_linear(source.rgb), 0.0, 1.0);
+    color = vec4(to_srgb(simulated), source.a);
+}
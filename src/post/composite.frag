@@ -0,0 +1,11 @@
+#version 150
+
+in vec2 uv;
+out vec4 color;
+uniform sampler2D frame;
+
+void main() {
+    // The frame holds premultiplied glow whose alpha can add up past one, which would make the target go negative.
+    color = texture(frame, uv);
+    color.a = min(color.a, 1.0);
+}
@@ -0,0 +1,9 @@
+#version 150
+
+in vec2 uv;
+out vec4 color;
+uniform sampler2D frame;
+
+void main() {
+    color = texture(frame, uv);
+}
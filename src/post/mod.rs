@@ -0,0 +1,478 @@
+use glium::{self, Surface};
+use glium::texture::{MipmapsOption, Texture2d, UncompressedFloatFormat};
+
+use program;
+use seed::Rng;
+
+/// A kind of color vision deficiency to simulate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorBlindness {
+    /// Missing red cones.
+    Protanopia,
+    /// Missing green cones, the most common kind.
+    Deuteranopia,
+    /// Missing blue cones.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// The matrix from "A Physiologically-based Model for Simulation of Color Vision Deficiency" by Machado et al.
+    /// at full severity, which applies to linear RGB. It is written row by row.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorBlindness::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            ColorBlindness::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            ColorBlindness::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+    position: [f32; 2],
+}
+
+implement_vertex!(Vertex, position);
+
+/// A quad that covers the whole target as a triangle strip.
+fn quad<F>(facade: &F) -> glium::VertexBuffer<Vertex>
+where
+    F: glium::backend::Facade,
+{
+    program::allocate(
+        glium::VertexBuffer::new(
+            facade,
+            &[
+                Vertex { position: [-1.0, -1.0] },
+                Vertex { position: [1.0, -1.0] },
+                Vertex { position: [-1.0, 1.0] },
+                Vertex { position: [1.0, 1.0] },
+            ],
+        ),
+        "the fullscreen quad",
+    )
+}
+
+/// A ColorBlindFilter shows how a rendered frame looks with a color vision deficiency, so palettes can be checked
+/// for categories that become indistinguishable.
+///
+/// Render the graph into a texture (such as the texture of a `GraphView`) and then apply the filter to draw the
+/// simulated result to the screen.
+pub struct ColorBlindFilter {
+    program: glium::Program,
+    quad: glium::VertexBuffer<Vertex>,
+}
+
+impl ColorBlindFilter {
+    /// Build the filter's program.
+    pub fn new<F>(facade: &F) -> Self
+    where
+        F: glium::backend::Facade,
+    {
+        ColorBlindFilter {
+            program: program::build(
+                facade,
+                "color blindness",
+                include_str!("fullscreen.vert"),
+                include_str!("colorblind.frag"),
+                None,
+            ),
+            quad: quad(facade),
+        }
+    }
+
+    /// Draw `frame` over the whole target as it would be seen with a color vision deficiency.
+    pub fn apply<S>(&self, frame: &Texture2d, target: &mut S, deficiency: ColorBlindness)
+    where
+        S: Surface,
+    {
+        let m = deficiency.matrix();
+        // GLSL matrices are given column by column.
+        let simulation = [
+            [m[0][0], m[1][0], m[2][0]],
+            [m[0][1], m[1][1], m[2][1]],
+            [m[0][2], m[1][2], m[2][2]],
+        ];
+        let uniforms = uniform! {
+            frame: frame.sampled()
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+            simulation: simulation,
+        };
+        let result = target.draw(
+            &self.quad,
+            glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+            &self.program,
+            &uniforms,
+            &Default::default(),
+        );
+        if let Err(e) = result {
+            error!("failed to apply the color blindness filter: {}", e);
+        }
+    }
+}
+
+/// The modelview and projection matrices a frame was rendered with.
+type Matrices = ([[f32; 3]; 3], [[f32; 3]; 3]);
+
+/// An Accumulator supersamples a still scene by averaging frames rendered with subpixel jitter, for crisp
+/// screenshots and idle displays.
+///
+/// Every frame, call `begin` with the matrices the scene is drawn with and whether anything in it changed. While it
+/// returns a projection, clear and render the scene with that projection into `frame`, call `accumulate`, and draw
+/// the average to the screen with `resolve`. The average starts over whenever the matrices change or `changed` is
+/// true, so moving the camera is as responsive as drawing directly. Once `max_samples` frames are averaged `begin`
+/// returns `None` and only `resolve` is needed.
+pub struct Accumulator {
+    program: glium::Program,
+    quad: glium::VertexBuffer<Vertex>,
+    frame: Texture2d,
+    sum: Texture2d,
+    samples: u32,
+    matrices: Option<Matrices>,
+    /// How many jittered frames are averaged before the result stops changing.
+    pub max_samples: u32,
+    /// Shifts the jitter pattern, so the same seed always averages the same subpixel offsets. Zero keeps the plain
+    /// Halton sequence.
+    pub seed: u64,
+}
+
+impl Accumulator {
+    /// Make an Accumulator for a target of the given size in pixels.
+    pub fn new<F>(facade: &F, width: u32, height: u32) -> Self
+    where
+        F: glium::backend::Facade,
+    {
+        Accumulator {
+            program: program::build(
+                facade,
+                "accumulate",
+                include_str!("fullscreen.vert"),
+                include_str!("copy.frag"),
+                None,
+            ),
+            quad: quad(facade),
+            frame: program::allocate(Texture2d::empty(facade, width, height), "a frame texture"),
+            sum: accumulation_texture(facade, width, height),
+            samples: 0,
+            matrices: None,
+            max_samples: 16,
+            seed: 0,
+        }
+    }
+
+    /// Change the size of the textures if it is different, which starts the average over.
+    pub fn resize<F>(&mut self, facade: &F, width: u32, height: u32)
+    where
+        F: glium::backend::Facade,
+    {
+        if self.frame.dimensions() != (width, height) {
+            self.frame = program::allocate(Texture2d::empty(facade, width, height), "a frame texture");
+            self.sum = accumulation_texture(facade, width, height);
+            self.reset();
+        }
+    }
+
+    /// Start the average over with the next frame.
+    pub fn reset(&mut self) {
+        self.samples = 0;
+    }
+
+    /// Get the number of frames in the average.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Whether the average is complete and nothing needs to be rendered.
+    pub fn converged(&self) -> bool {
+        self.samples >= self.max_samples
+    }
+
+    /// Get the average of the accumulated frames, which is a floating point texture.
+    pub fn average(&self) -> &Texture2d {
+        &self.sum
+    }
+
+    /// Get the texture the next jittered frame should be rendered into.
+    pub fn frame(&self) -> &Texture2d {
+        &self.frame
+    }
+
+    /// Start a frame, getting the jittered projection to render it with or `None` if the average is complete.
+    pub fn begin(
+        &mut self,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        changed: bool,
+    ) -> Option<[[f32; 3]; 3]> {
+        if changed || self.matrices != Some((modelview, projection)) {
+            self.matrices = Some((modelview, projection));
+            self.reset();
+        }
+        if self.converged() {
+            return None;
+        }
+        // The first frame is not jittered so that the average starts out like the scene drawn directly.
+        let (x, y) = if self.samples == 0 {
+            (0.0, 0.0)
+        } else {
+            // Rotating the whole sequence by a seeded offset keeps its even coverage of the pixel.
+            let (sx, sy) = if self.seed == 0 {
+                (0.0, 0.0)
+            } else {
+                let mut rng = Rng::new(self.seed);
+                (rng.next_f32(), rng.next_f32())
+            };
+            (
+                (halton(self.samples, 2) + sx).fract() - 0.5,
+                (halton(self.samples, 3) + sy).fract() - 0.5,
+            )
+        };
+        let (width, height) = self.frame.dimensions();
+        let mut jittered = projection;
+        jittered[2][0] += 2.0 * x / width as f32;
+        jittered[2][1] += 2.0 * y / height as f32;
+        Some(jittered)
+    }
+
+    /// Add the frame that was just rendered into `frame` to the average.
+    pub fn accumulate(&mut self) {
+        // Blending by a constant of one over the number of samples keeps a running average.
+        let weight = 1.0 / (self.samples + 1) as f32;
+        let blend = glium::BlendingFunction::Addition {
+            source: glium::LinearBlendingFactor::ConstantAlpha,
+            destination: glium::LinearBlendingFactor::OneMinusConstantAlpha,
+        };
+        let params = glium::DrawParameters {
+            blend: glium::Blend {
+                color: blend,
+                alpha: blend,
+                constant_value: (0.0, 0.0, 0.0, weight),
+            },
+            ..Default::default()
+        };
+        let uniforms = uniform! {
+            frame: self.frame.sampled()
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+        };
+        let result = self.sum.as_surface().draw(
+            &self.quad,
+            glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+            &self.program,
+            &uniforms,
+            &params,
+        );
+        match result {
+            Ok(()) => self.samples += 1,
+            Err(e) => error!("failed to accumulate a frame: {}", e),
+        }
+    }
+
+    /// Draw the average of the accumulated frames over the whole target.
+    pub fn resolve<S>(&self, target: &mut S)
+    where
+        S: Surface,
+    {
+        let uniforms = uniform! {
+            frame: self.sum.sampled()
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+        };
+        let result = target.draw(
+            &self.quad,
+            glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+            &self.program,
+            &uniforms,
+            &Default::default(),
+        );
+        if let Err(e) = result {
+            error!("failed to resolve the accumulated frames: {}", e);
+        }
+    }
+}
+
+/// A floating point texture, so the running average doesn't lose precision as samples are added.
+fn accumulation_texture<F>(facade: &F, width: u32, height: u32) -> Texture2d
+where
+    F: glium::backend::Facade,
+{
+    program::allocate(
+        Texture2d::empty_with_format(
+            facade,
+            UncompressedFloatFormat::F32F32F32F32,
+            MipmapsOption::NoMipmap,
+            width,
+            height,
+        ),
+        "the accumulation texture",
+    )
+}
+
+/// The element of the Halton low discrepancy sequence at `index` in `base`, from 0 to 1.
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/// A GroupCompositor draws groups of elements into an offscreen texture one at a time and combines the groups by
+/// keeping the brightest of them at every pixel, which `Scene::render_grouped` uses for occlusion groups.
+///
+/// The groups are combined in a second offscreen texture that starts out transparent, and the result is blended over
+/// the target as premultiplied color at the end. This way the maximum is only taken between groups and never against
+/// the background, so dark glows still show up on light backgrounds.
+pub struct GroupCompositor {
+    copy: glium::Program,
+    composite: glium::Program,
+    quad: glium::VertexBuffer<Vertex>,
+    group: Texture2d,
+    combined: Texture2d,
+}
+
+impl GroupCompositor {
+    /// Make a GroupCompositor for a target of the given size in pixels.
+    pub fn new<F>(facade: &F, width: u32, height: u32) -> Self
+    where
+        F: glium::backend::Facade,
+    {
+        GroupCompositor {
+            copy: program::build(
+                facade,
+                "group maximum",
+                include_str!("fullscreen.vert"),
+                include_str!("copy.frag"),
+                None,
+            ),
+            composite: program::build(
+                facade,
+                "group composite",
+                include_str!("fullscreen.vert"),
+                include_str!("composite.frag"),
+                None,
+            ),
+            quad: quad(facade),
+            group: group_texture(facade, width, height),
+            combined: group_texture(facade, width, height),
+        }
+    }
+
+    /// Change the size of the textures if it is different, which should be done when the target is resized.
+    pub fn resize<F>(&mut self, facade: &F, width: u32, height: u32)
+    where
+        F: glium::backend::Facade,
+    {
+        if self.group.dimensions() != (width, height) {
+            self.group = group_texture(facade, width, height);
+            self.combined = group_texture(facade, width, height);
+        }
+    }
+
+    /// Call `draw` with each group from `0` to `groups` and a cleared surface to draw it on, combining the groups by
+    /// taking the maximum of each channel and blending the result over `target`.
+    ///
+    /// `draw` should add up the glow of the group with premultiplied color, like the additive blending of
+    /// `Renderer::set_additive` does.
+    pub fn render<S, F>(&self, target: &mut S, groups: usize, mut draw: F)
+    where
+        S: Surface,
+        F: FnMut(usize, &mut glium::framebuffer::SimpleFrameBuffer),
+    {
+        let max = glium::BlendingFunction::Max;
+        let max_params = glium::DrawParameters {
+            blend: glium::Blend {
+                color: max,
+                alpha: max,
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            ..Default::default()
+        };
+        let over = glium::BlendingFunction::Addition {
+            source: glium::LinearBlendingFactor::One,
+            destination: glium::LinearBlendingFactor::OneMinusSourceAlpha,
+        };
+        let over_params = glium::DrawParameters {
+            blend: glium::Blend {
+                color: over,
+                alpha: over,
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            ..Default::default()
+        };
+
+        let mut combined = self.combined.as_surface();
+        combined.clear_color(0.0, 0.0, 0.0, 0.0);
+        for group in 0..groups {
+            {
+                let mut surface = self.group.as_surface();
+                surface.clear_color(0.0, 0.0, 0.0, 0.0);
+                draw(group, &mut surface);
+            }
+            if let Err(e) = self.blit(&mut combined, &self.copy, &self.group, &max_params) {
+                error!("failed to combine occlusion group {}: {}", group, e);
+            }
+        }
+        if let Err(e) = self.blit(target, &self.composite, &self.combined, &over_params) {
+            error!("failed to composite occlusion groups: {}", e);
+        }
+    }
+
+    /// Draw `texture` over the whole of `target` with `program`.
+    fn blit<S>(
+        &self,
+        target: &mut S,
+        program: &glium::Program,
+        texture: &Texture2d,
+        params: &glium::DrawParameters,
+    ) -> Result<(), glium::DrawError>
+    where
+        S: Surface,
+    {
+        let uniforms = uniform! {
+            frame: texture.sampled()
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+        };
+        target.draw(
+            &self.quad,
+            glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+            program,
+            &uniforms,
+            params,
+        )
+    }
+}
+
+/// A half float texture, so glow that adds up within a group isn't clamped before the groups are combined.
+fn group_texture<F>(facade: &F, width: u32, height: u32) -> Texture2d
+where
+    F: glium::backend::Facade,
+{
+    program::allocate(
+        Texture2d::empty_with_format(
+            facade,
+            UncompressedFloatFormat::F16F16F16F16,
+            MipmapsOption::NoMipmap,
+            width,
+            height,
+        ),
+        "a group texture",
+    )
+}
@@ -0,0 +1,174 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use glium;
+use rayon::prelude::*;
+
+use render2::{Node, QBezier};
+use routing::Rect;
+use scene::{self, Scene, SceneEdge, SceneNode};
+use spline;
+use style::StyleSheet;
+use theme::Theme;
+use visibility::Visibility;
+
+/// Vertex data built on worker threads that is ready to be uploaded on the render thread.
+#[derive(Clone, Debug)]
+pub struct Prepared {
+    /// One vertex for every node.
+    pub nodes: Vec<Node>,
+    /// Two vertices for every edge.
+    pub edges: Vec<Node>,
+    /// Which nodes overlap the view, for `Renderer::render_nodes_filtered`.
+    pub visible_nodes: Visibility,
+    /// Which edges overlap the view, for `Renderer::render_edges_round_filtered`.
+    pub visible_edges: Visibility,
+}
+
+impl Prepared {
+    /// Upload the node and edge vertices, which is the only part of preparing a scene done on the render thread.
+    pub fn upload<F>(
+        &self,
+        facade: &F,
+    ) -> Result<(glium::VertexBuffer<Node>, glium::VertexBuffer<Node>), glium::vertex::BufferCreationError>
+    where
+        F: glium::backend::Facade,
+    {
+        Ok((
+            glium::VertexBuffer::new(facade, &self.nodes)?,
+            glium::VertexBuffer::new(facade, &self.edges)?,
+        ))
+    }
+}
+
+/// A copy of everything in a Scene that is needed to build its vertices, which can be sent to worker threads.
+///
+/// Taking a snapshot copies the nodes and edges but resolves nothing, so it is cheap compared to preparing it.
+#[derive(Clone, Debug)]
+pub struct SceneSnapshot {
+    theme: Theme,
+    styles: StyleSheet,
+    nodes: Vec<SceneNode>,
+    edges: Vec<SceneEdge>,
+    positions: Vec<[f32; 2]>,
+}
+
+impl SceneSnapshot {
+    /// Copy the nodes, edges, and styles of a scene.
+    pub fn new(scene: &Scene) -> Self {
+        let mut positions = Vec::new();
+        let nodes = scene
+            .nodes()
+            .map(|(i, node)| {
+                if positions.len() <= i {
+                    positions.resize(i + 1, [0.0; 2]);
+                }
+                positions[i] = node.position;
+                node.clone()
+            })
+            .collect();
+        SceneSnapshot {
+            theme: *scene.theme(),
+            styles: scene.styles().clone(),
+            nodes: nodes,
+            edges: scene.edges().map(|(_, edge)| edge.clone()).collect(),
+            positions: positions,
+        }
+    }
+
+    /// Resolve the styles of every element into vertices in parallel and cull them against `view` if there is one.
+    ///
+    /// The vertices are in the same order as `Scene::node_vertices` and `Scene::edge_vertices` after an update.
+    pub fn prepare(&self, view: Option<&Rect>) -> Prepared {
+        let nodes = self.nodes
+            .par_iter()
+            .map(|node| {
+                let style = scene::resolve(&self.theme, &self.styles, &node.style, node.selected, &self.theme.node);
+                style.node2(node.position)
+            })
+            .collect::<Vec<_>>();
+        let edges = self.edges
+            .par_iter()
+            .flat_map(|edge| {
+                let style = scene::resolve(&self.theme, &self.styles, &edge.style, edge.selected, &self.theme.edge);
+                vec![
+                    style.node2(self.positions[edge.from]),
+                    style.node2(self.positions[edge.to]),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let visible_nodes = cull(&nodes, 1, view);
+        let visible_edges = cull(&edges, 2, view);
+        Prepared {
+            nodes: nodes,
+            edges: edges,
+            visible_nodes: visible_nodes,
+            visible_edges: visible_edges,
+        }
+    }
+
+    /// Prepare the snapshot on the rayon thread pool, so the render thread can keep presenting frames meanwhile.
+    pub fn spawn(self, view: Option<Rect>) -> PendingPrepare {
+        let (sender, receiver) = mpsc::channel();
+        rayon::spawn(move || {
+            // The receiver being gone just means the result isn't wanted anymore.
+            let _ = sender.send(self.prepare(view.as_ref()));
+        });
+        PendingPrepare { receiver: receiver }
+    }
+}
+
+/// Vertex data being prepared in the background by `SceneSnapshot::spawn`.
+pub struct PendingPrepare {
+    receiver: Receiver<Prepared>,
+}
+
+impl PendingPrepare {
+    /// Take the prepared vertices if they are ready without blocking, which can be checked once every frame.
+    pub fn try_take(&self) -> Option<Prepared> {
+        match self.receiver.try_recv() {
+            Ok(prepared) => Some(prepared),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                error!("a worker thread preparing vertices stopped without finishing");
+                None
+            }
+        }
+    }
+
+    /// Block until the prepared vertices are ready.
+    pub fn wait(self) -> Option<Prepared> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Find which elements (`per_element` vertices each) have a glow that overlaps `view`, in parallel.
+///
+/// Every element is visible if there is no view.
+pub fn cull(vertices: &[Node], per_element: usize, view: Option<&Rect>) -> Visibility {
+    let mut visibility = Visibility::new(vertices.len() / per_element);
+    if let Some(view) = view {
+        let visible = vertices
+            .par_chunks(per_element)
+            .map(|element| {
+                element
+                    .iter()
+                    .map(|n| Rect::around(n).expand(n.falloff_radius))
+                    .fold(None, |acc: Option<Rect>, r| Some(acc.map_or(r, |acc| acc.union(&r))))
+                    .map_or(false, |bounds| bounds.intersects(view))
+            })
+            .collect::<Vec<_>>();
+        visibility.filter(|element| visible[element]);
+    }
+    visibility
+}
+
+/// Turn many chains of waypoints into splines with `spline::catmull_rom` in parallel, all in one list of curves.
+pub fn catmull_rom_all<P>(chains: &[P]) -> Vec<QBezier>
+where
+    P: AsRef<[Node]> + Sync,
+{
+    chains
+        .par_iter()
+        .flat_map(|chain| spline::catmull_rom(chain.as_ref()))
+        .collect()
+}
@@ -0,0 +1,38 @@
+use glium;
+
+/// Build one of the renderers' programs, logging the outcome so broken drivers can be diagnosed from logs.
+///
+/// Panics if the program fails to compile or link since the renderer can't draw without it.
+pub(crate) fn build<F>(
+    facade: &F,
+    name: &str,
+    vertex: &str,
+    fragment: &str,
+    geometry: Option<&str>,
+) -> glium::Program
+where
+    F: glium::backend::Facade,
+{
+    match glium::Program::from_source(facade, vertex, fragment, geometry) {
+        Ok(program) => {
+            debug!("built the {} program", name);
+            program
+        }
+        Err(e) => {
+            error!("failed to build the {} program: {}", name, e);
+            panic!("failed to build the {} program: {}", name, e)
+        }
+    }
+}
+
+/// Unwrap the result of creating a GPU buffer or texture that the renderers can't draw without, logging the error
+/// before panicking like `build` does.
+pub(crate) fn allocate<T, E>(result: Result<T, E>, what: &str) -> T
+where
+    E: ::std::fmt::Display,
+{
+    result.unwrap_or_else(|e| {
+        error!("failed to allocate {}: {}", what, e);
+        panic!("failed to allocate {}: {}", what, e)
+    })
+}
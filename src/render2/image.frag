@@ -0,0 +1,18 @@
+#version 150
+
+in vec2 uv;
+uniform sampler2D image;
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the emitted triangles instead of the image.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    color = texture(image, uv);
+}
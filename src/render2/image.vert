@@ -0,0 +1,12 @@
+#version 150
+
+in vec2 position;
+in vec2 tex_coords;
+out vec2 uv;
+uniform mat3 modelview;
+uniform mat3 projection;
+
+void main() {
+    uv = tex_coords;
+    gl_Position = vec4((projection * modelview * vec3(position, 1.0)).xy, 0.0, 1.0);
+}
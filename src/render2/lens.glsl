@@ -0,0 +1,22 @@
+uniform vec2 lens_center;
+uniform float lens_radius;
+uniform float lens_strength;
+
+// Sarkar-Brown fisheye, which magnifies positions near the lens center and compresses them toward its edge.
+// The local magnification is returned so that radii can be scaled to match.
+vec2 lens(vec2 p, out float magnification) {
+    magnification = 1.0;
+    if (lens_strength <= 0.0 || lens_radius <= 0.0) {
+        return p;
+    }
+    vec2 offset = p - lens_center;
+    float x = length(offset) / lens_radius;
+    if (x >= 1.0) {
+        return p;
+    }
+    // The mapping g(x) = (d + 1) x / (d x + 1) stretches by g(x) / x around the center and by
+    // g'(x) = (d + 1) / (d x + 1)^2 away from it. The radial stretch drops to 1 / (d + 1) at the edge of the lens
+    // while nothing outside is scaled, so radii follow the stretch around the center, which is continuous there.
+    magnification = (lens_strength + 1.0) / (lens_strength * x + 1.0);
+    return lens_center + offset * magnification;
+}
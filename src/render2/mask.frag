@@ -0,0 +1,8 @@
+#version 150
+
+out vec4 color;
+
+void main() {
+    // Only the stencil is written.
+    color = vec4(1.0);
+}
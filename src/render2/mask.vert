@@ -0,0 +1,13 @@
+#version 150
+
+in vec2 position;
+out vec2 uv;
+uniform mat3 modelview;
+uniform mat3 projection;
+
+void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
+    uv = 0.5 * position + 0.5;
+    gl_Position = vec4((projection * vec3(view_position, 1.0)).xy, 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 150
+
+in vec2 uv;
+out vec4 color;
+uniform sampler2D mask;
+
+void main() {
+    if (texture(mask, uv).a < 0.5) {
+        discard;
+    }
+    // Only the stencil is written.
+    color = vec4(1.0);
+}
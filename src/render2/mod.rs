@@ -1,5 +1,17 @@
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+
+use glium::uniforms::UniformValue;
 use glium::{self, Surface};
-use cgmath;
+use cgmath::{self, Matrix3, SquareMatrix, Vector3};
+use debug::{DebugUniforms, DebugView};
+use uniforms::ExtraUniforms;
+use program;
+use routing::Rect;
+use validate::{self, Validate, ValidationError};
+use visibility::Visibility;
+use style::StyleSheet;
+use tiles::Tiles;
 
 /// Node is used to pass nodes into the renderer.
 #[derive(Copy, Clone, Debug)]
@@ -27,6 +39,31 @@ impl From<cgmath::Point2<f32>> for Node {
     }
 }
 
+impl Node {
+    /// Linearly interpolate every attribute of two nodes, where `t` of `0.0` gives `self` and `1.0` gives `other`.
+    pub fn lerp(&self, other: &Node, t: f32) -> Node {
+        let lerp4 = |a: [f32; 4], b: [f32; 4]| {
+            [
+                a[0] + (b[0] - a[0]) * t,
+                a[1] + (b[1] - a[1]) * t,
+                a[2] + (b[2] - a[2]) * t,
+                a[3] + (b[3] - a[3]) * t,
+            ]
+        };
+        Node {
+            position: [
+                self.position[0] + (other.position[0] - self.position[0]) * t,
+                self.position[1] + (other.position[1] - self.position[1]) * t,
+            ],
+            inner_color: lerp4(self.inner_color, other.inner_color),
+            falloff: self.falloff + (other.falloff - self.falloff) * t,
+            falloff_color: lerp4(self.falloff_color, other.falloff_color),
+            falloff_radius: self.falloff_radius + (other.falloff_radius - self.falloff_radius) * t,
+            inner_radius: self.inner_radius + (other.inner_radius - self.inner_radius) * t,
+        }
+    }
+}
+
 implement_vertex!(
     Node,
     position,
@@ -73,7 +110,354 @@ implement_vertex!(
     inner_radius1
 );
 
+/// RectNode is used to pass rounded rectangle nodes into the renderer, such as the boxes of box-and-arrow diagrams.
+///
+/// The width and height are set independently, so unlike round nodes they can hold a line of text.
+#[derive(Copy, Clone, Debug)]
+pub struct RectNode {
+    pub position: [f32; 2],
+    /// Half of the width and height of the solid part of the node.
+    pub half_size: [f32; 2],
+    /// The radius of the corners, which is limited to half of the shorter side.
+    pub corner_radius: f32,
+    pub inner_color: [f32; 4],
+    /// Decreasing falloff makes the nodes brightness more centered at the middle and increasing it makes it consistent.
+    pub falloff: f32,
+    pub falloff_color: [f32; 4],
+    pub falloff_radius: f32,
+}
+
+implement_vertex!(
+    RectNode,
+    position,
+    half_size,
+    corner_radius,
+    inner_color,
+    falloff,
+    falloff_color,
+    falloff_radius
+);
+
+impl RectNode {
+    /// Resize the node to fit a label set in a monospace font, where every character is `char_width` wide and every
+    /// line is `line_height` tall, with `padding` around the text on every side.
+    pub fn fit_label(&mut self, label: &str, char_width: f32, line_height: f32, padding: f32) {
+        let columns = label.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let rows = label.lines().count().max(1);
+        self.half_size = [
+            0.5 * columns as f32 * char_width + padding,
+            0.5 * rows as f32 * line_height + padding,
+        ];
+    }
+}
+
+/// PieNode is used to pass nodes whose inner disc is divided into up to four colored segments into the renderer,
+/// such as the share of traffic per category.
+///
+/// Segments go clockwise from the top and take up their share of the sum of all shares.
+#[derive(Copy, Clone, Debug)]
+pub struct PieNode {
+    pub position: [f32; 2],
+    /// The relative size of each segment, where segments with a share of zero are not drawn.
+    pub shares: [f32; 4],
+    pub color0: [f32; 4],
+    pub color1: [f32; 4],
+    pub color2: [f32; 4],
+    pub color3: [f32; 4],
+    /// The radius of the hole in the middle as a fraction of the inner radius, which makes a donut when above zero.
+    pub hole: f32,
+    /// Decreasing falloff makes the nodes brightness more centered at the middle and increasing it makes it consistent.
+    pub falloff: f32,
+    pub falloff_color: [f32; 4],
+    pub falloff_radius: f32,
+    pub inner_radius: f32,
+}
+
+implement_vertex!(
+    PieNode,
+    position,
+    shares,
+    color0,
+    color1,
+    color2,
+    color3,
+    hole,
+    falloff,
+    falloff_color,
+    falloff_radius,
+    inner_radius
+);
+
+impl PieNode {
+    /// Set the share and color of every segment, leaving any unused segments empty.
+    ///
+    /// Panics if there are more than four segments.
+    pub fn set_segments(&mut self, segments: &[(f32, [f32; 4])]) {
+        assert!(segments.len() <= 4, "a PieNode has at most four segments");
+        let mut shares = [0.0; 4];
+        let mut colors = [[0.0; 4]; 4];
+        for (i, &(share, color)) in segments.iter().enumerate() {
+            shares[i] = share;
+            colors[i] = color;
+        }
+        self.shares = shares;
+        self.color0 = colors[0];
+        self.color1 = colors[1];
+        self.color2 = colors[2];
+        self.color3 = colors[3];
+    }
+}
+
+/// RingNode is used to pass progress rings into the renderer, which are drawn around nodes to show completion or health.
+///
+/// The ring is filled clockwise from the top up to `progress` and the rest of it is drawn in `track_color`.
+#[derive(Copy, Clone, Debug)]
+pub struct RingNode {
+    pub position: [f32; 2],
+    /// The distance from the center to the middle of the ring.
+    pub radius: f32,
+    pub width: f32,
+    /// How much of the ring is filled, from `0.0` to `1.0`.
+    pub progress: f32,
+    pub color: [f32; 4],
+    /// The color of the part of the ring that is not filled, which is often transparent or faint.
+    pub track_color: [f32; 4],
+    /// Decreasing falloff makes the nodes brightness more centered at the middle and increasing it makes it consistent.
+    pub falloff: f32,
+    pub falloff_color: [f32; 4],
+    pub falloff_radius: f32,
+}
+
+implement_vertex!(
+    RingNode,
+    position,
+    radius,
+    width,
+    progress,
+    color,
+    track_color,
+    falloff,
+    falloff_color,
+    falloff_radius
+);
+
+/// Ribbon is used to pass bundles of edges into the renderer, which are drawn as wide bands along a quadratic bezier.
+///
+/// The band is `fill` times as opaque in the middle as at its edges, which glow outwards over `falloff_radius`.
+/// Its width and color are interpolated from the first end to the last. See `analysis::ribbons` to bundle edges
+/// between clusters into ribbons.
+#[derive(Copy, Clone, Debug)]
+pub struct Ribbon {
+    pub position0: [f32; 2],
+    /// The control point of the curve.
+    pub position1: [f32; 2],
+    pub position2: [f32; 2],
+    pub width0: f32,
+    pub width1: f32,
+    pub color0: [f32; 4],
+    pub color1: [f32; 4],
+    /// How opaque the middle of the band is compared to its edges, from `0.0` to `1.0`.
+    pub fill: f32,
+    pub falloff_radius: f32,
+}
+
+implement_vertex!(
+    Ribbon,
+    position0,
+    position1,
+    position2,
+    width0,
+    width1,
+    color0,
+    color1,
+    fill,
+    falloff_radius
+);
+
+/// The shape drawn where two segments of a polyline meet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Join {
+    Round,
+    /// Sharp corners, which are cut off once they reach four times the radius past the corner.
+    Miter,
+}
+
+/// The shape drawn at the two ends of a polyline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cap {
+    Round,
+    Flat,
+}
+
+/// A fisheye lens that magnifies the region around its center while compressing the rest of its area,
+/// so dense regions can be explored without losing their surroundings.
+///
+/// The lens is applied after the modelview matrix, so `center` and `radius` are in view space.
+/// Positions outside of the radius are unaffected.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Lens {
+    pub center: [f32; 2],
+    pub radius: f32,
+    /// How strongly the center is magnified, where `0.0` has no effect and the center is magnified `strength + 1` times.
+    pub strength: f32,
+}
+
+/// Noise that makes the glow of nodes and edges flicker, such as to mark elements that are live or unstable.
+///
+/// The alpha of every pixel is scaled by between `1.0 - amount` and `1.0` following value noise that moves through
+/// time. Set it with `Renderer::set_shimmer` before drawing the elements that should flicker and again with a new
+/// `time` every frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shimmer {
+    /// The current time in seconds.
+    pub time: f32,
+    /// How much of the alpha the noise can take away, from `0.0` to `1.0`.
+    pub amount: f32,
+    /// How quickly the noise changes, in lattice cells per second.
+    pub speed: f32,
+    /// The size of the lattice cells of the noise in pixels.
+    pub scale: f32,
+}
+
+impl Default for Shimmer {
+    fn default() -> Shimmer {
+        Shimmer {
+            time: 0.0,
+            amount: 0.3,
+            speed: 4.0,
+            scale: 6.0,
+        }
+    }
+}
+
+/// A region that drawing is clipped to, set with `Renderer::set_mask`.
+pub enum Mask {
+    /// A polygon in the same space as the nodes, such as the outline of a country on a map.
+    ///
+    /// It may be concave. Self-intersecting polygons are filled with the even-odd rule.
+    Polygon(Vec<[f32; 2]>),
+    /// A texture stretched over the whole target, where drawing is allowed wherever its alpha is at least one half.
+    Texture(glium::texture::Texture2d),
+}
+
+/// How many straight segments each edge of a polygon mask is split into.
+const MASK_EDGE_SEGMENTS: usize = 16;
+
+// A renderer only ever holds one mask, so the size of the variants doesn't matter.
+#[allow(clippy::large_enum_variant)]
+enum MaskData {
+    Polygon(glium::VertexBuffer<MaskVertex>),
+    Texture(glium::texture::Texture2d),
+}
+
+#[derive(Copy, Clone, Debug)]
+struct MaskVertex {
+    position: [f32; 2],
+}
+
+implement_vertex!(MaskVertex, position);
+
+#[derive(Copy, Clone, Debug)]
+struct ImageVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(ImageVertex, position, tex_coords);
+
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Add the shared GLSL in `library` to a shader, right after its `#version` line.
+fn splice(source: &str, library: &str) -> String {
+    let split = source.find('\n').map_or(source.len(), |i| i + 1);
+    format!("{}{}{}", &source[..split], library, &source[split..])
+}
+
+/// Add the `lens` function and its uniforms to a vertex shader.
+fn lensed(source: &str) -> String {
+    splice(source, include_str!("lens.glsl"))
+}
+
+/// Add the `shimmer` function and its uniforms to a fragment shader.
+fn shimmered(source: &str) -> String {
+    splice(source, include_str!("shimmer.glsl"))
+}
+
+/// StyledNode is a node that gets its appearance from a named style in a StyleSheet.
+#[derive(Clone, Debug)]
+pub struct StyledNode {
+    pub position: [f32; 2],
+    pub style: String,
+}
+
+impl StyledNode {
+    /// Resolve the style of this node against a StyleSheet to get a Node ready for rendering.
+    pub fn resolve(&self, sheet: &StyleSheet) -> Node {
+        sheet.resolve(&self.style).node2(self.position)
+    }
+}
+
+/// StyledQBezier is a quadratic bezier curve that gets its appearance from named styles in a StyleSheet.
+///
+/// `style0` is used at `position0` and `style1` is used at `position2`.
+#[derive(Clone, Debug)]
+pub struct StyledQBezier {
+    pub position0: [f32; 2],
+    pub position1: [f32; 2],
+    pub position2: [f32; 2],
+    pub style0: String,
+    pub style1: String,
+}
+
+impl StyledQBezier {
+    /// Resolve the styles of this curve against a StyleSheet to get a QBezier ready for rendering.
+    pub fn resolve(&self, sheet: &StyleSheet) -> QBezier {
+        let style0 = sheet.resolve(&self.style0);
+        let style1 = sheet.resolve(&self.style1);
+        QBezier {
+            position0: self.position0,
+            position1: self.position1,
+            position2: self.position2,
+            inner_color0: style0.inner_color,
+            inner_color1: style1.inner_color,
+            falloff_color0: style0.falloff_color,
+            falloff_color1: style1.falloff_color,
+            falloff0: style0.falloff,
+            falloff1: style1.falloff,
+            falloff_radius0: style0.falloff_radius,
+            falloff_radius1: style1.falloff_radius,
+            inner_radius0: style0.inner_radius,
+            inner_radius1: style1.inner_radius,
+        }
+    }
+}
+
+/// GlowNode lets application types be rendered as nodes without building an intermediate `Vec<Node>`.
+///
+/// Nodes are converted while they are written into a vertex buffer that the Renderer reuses between frames.
+pub trait GlowNode {
+    fn glow_node(&self) -> Node;
+}
+
+impl GlowNode for Node {
+    fn glow_node(&self) -> Node {
+        *self
+    }
+}
+
+impl<T> GlowNode for &T
+where
+    T: GlowNode,
+{
+    fn glow_node(&self) -> Node {
+        (*self).glow_node()
+    }
+}
+
 /// A Renderer is tied to the lifetime of the glium Display and making one builds a GLSL program internally.
+///
+/// Draw calls that fail are logged and skipped, so one bad frame doesn't take down the application. Failing to build
+/// a program or to allocate a buffer is logged and then panics, since nothing can be drawn without them.
 pub struct Renderer<'a, D>
 where
     D: 'a,
@@ -82,9 +466,28 @@ where
     node_program: glium::Program,
     round_edge_program: glium::Program,
     flat_edge_program: glium::Program,
+    sdf_node_program: glium::Program,
+    sprite_program: glium::Program,
     round_qbezier_program: glium::Program,
     flat_qbezier_program: glium::Program,
+    polyline_program: glium::Program,
+    rect_program: glium::Program,
+    pie_program: glium::Program,
+    ring_program: glium::Program,
+    ribbon_program: glium::Program,
+    image_program: glium::Program,
+    mask_program: glium::Program,
+    mask_texture_program: glium::Program,
+    occluder_program: glium::Program,
+    fullscreen: glium::VertexBuffer<MaskVertex>,
     params: glium::DrawParameters<'a>,
+    lens: Option<Lens>,
+    shimmer: Option<Shimmer>,
+    mask: Option<MaskData>,
+    scratch: RefCell<Option<glium::VertexBuffer<Node>>>,
+    debug: DebugView,
+    validate: bool,
+    warned_upload: Cell<bool>,
 }
 
 impl<'a, D> Renderer<'a, D>
@@ -95,43 +498,226 @@ where
     pub fn new(display: &'a D) -> Self {
         Renderer {
             display: display,
-            node_program: glium::Program::from_source(
+            node_program: program::build(
                 display,
-                include_str!("node.vert"),
-                include_str!("node.frag"),
+                "node",
+                &lensed(include_str!("node.vert")),
+                &shimmered(include_str!("node.frag")),
                 Some(include_str!("node.geom")),
-            ).unwrap(),
-            round_edge_program: glium::Program::from_source(
+            ),
+            round_edge_program: program::build(
                 display,
-                include_str!("node.vert"),
-                include_str!("node.frag"),
+                "round edge",
+                &lensed(include_str!("node.vert")),
+                &shimmered(include_str!("node.frag")),
                 Some(include_str!("round_edge.geom")),
-            ).unwrap(),
-            flat_edge_program: glium::Program::from_source(
+            ),
+            flat_edge_program: program::build(
                 display,
-                include_str!("node.vert"),
-                include_str!("node.frag"),
+                "flat edge",
+                &lensed(include_str!("node.vert")),
+                &shimmered(include_str!("node.frag")),
                 Some(include_str!("flat_edge.geom")),
-            ).unwrap(),
-            round_qbezier_program: glium::Program::from_source(
+            ),
+            sdf_node_program: program::build(
+                display,
+                "sdf node",
+                &lensed(include_str!("node.vert")),
+                &shimmered(include_str!("node_sdf.frag")),
+                Some(include_str!("node.geom")),
+            ),
+            sprite_program: program::build(
+                display,
+                "sprite",
+                &lensed(include_str!("sprite.vert")),
+                include_str!("sprite.frag"),
+                None,
+            ),
+            round_qbezier_program: program::build(
                 display,
-                include_str!("qbezier.vert"),
-                include_str!("qbezier.frag"),
+                "round qbezier",
+                &lensed(include_str!("qbezier.vert")),
+                &shimmered(include_str!("qbezier.frag")),
                 Some(include_str!("round_qbezier.geom")),
-            ).unwrap(),
-            flat_qbezier_program: glium::Program::from_source(
+            ),
+            flat_qbezier_program: program::build(
                 display,
-                include_str!("qbezier.vert"),
-                include_str!("qbezier.frag"),
+                "flat qbezier",
+                &lensed(include_str!("qbezier.vert")),
+                &shimmered(include_str!("qbezier.frag")),
                 Some(include_str!("flat_qbezier.geom")),
-            ).unwrap(),
+            ),
+            polyline_program: program::build(
+                display,
+                "polyline",
+                &lensed(include_str!("node.vert")),
+                include_str!("polyline.frag"),
+                Some(include_str!("polyline.geom")),
+            ),
+            rect_program: program::build(
+                display,
+                "rect",
+                &lensed(include_str!("rect.vert")),
+                include_str!("rect.frag"),
+                Some(include_str!("rect.geom")),
+            ),
+            pie_program: program::build(
+                display,
+                "pie",
+                &lensed(include_str!("pie.vert")),
+                include_str!("pie.frag"),
+                Some(include_str!("pie.geom")),
+            ),
+            ring_program: program::build(
+                display,
+                "ring",
+                &lensed(include_str!("ring.vert")),
+                include_str!("ring.frag"),
+                Some(include_str!("ring.geom")),
+            ),
+            ribbon_program: program::build(
+                display,
+                "ribbon",
+                &lensed(include_str!("ribbon.vert")),
+                include_str!("ribbon.frag"),
+                Some(include_str!("ribbon.geom")),
+            ),
+            image_program: program::build(
+                display,
+                "image",
+                include_str!("image.vert"),
+                include_str!("image.frag"),
+                None,
+            ),
+            mask_program: program::build(
+                display,
+                "mask",
+                &lensed(include_str!("mask.vert")),
+                include_str!("mask.frag"),
+                None,
+            ),
+            mask_texture_program: program::build(
+                display,
+                "mask texture",
+                &lensed(include_str!("mask.vert")),
+                include_str!("mask_texture.frag"),
+                None,
+            ),
+            occluder_program: program::build(
+                display,
+                "occluder",
+                &lensed(include_str!("node.vert")),
+                include_str!("occluder.frag"),
+                Some(include_str!("node.geom")),
+            ),
+            fullscreen: program::allocate(
+                glium::VertexBuffer::new(
+                    display,
+                    &[
+                        MaskVertex { position: [-1.0, -1.0] },
+                        MaskVertex { position: [1.0, -1.0] },
+                        MaskVertex { position: [-1.0, 1.0] },
+                        MaskVertex { position: [1.0, 1.0] },
+                    ],
+                ),
+                "the fullscreen quad",
+            ),
             params: glium::DrawParameters {
                 blend: glium::Blend::alpha_blending(),
                 ..Default::default()
             },
+            lens: None,
+            shimmer: None,
+            mask: None,
+            scratch: RefCell::new(None),
+            debug: DebugView::Off,
+            validate: false,
+            warned_upload: Cell::new(false),
         }
     }
 
+    /// Change what is drawn to diagnose the triangles emitted for every node and edge.
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        self.debug = view;
+    }
+
+    /// Check everything passed to the renderer for NaN or infinite values, negative radii, zero length edges, and
+    /// colors outside of `0.0..=1.0`. Each problem is logged as a warning with the index and field of the element,
+    /// and a draw with any problems is skipped.
+    ///
+    /// This is off by default since it costs a pass over every element each time something is drawn.
+    pub fn set_validation(&mut self, enabled: bool) {
+        self.validate = enabled;
+    }
+
+    /// Add the glow of everything drawn to what is already in the target instead of blending it over the top.
+    ///
+    /// Overlapping glow then gets brighter, which is best drawn into a floating point target that doesn't clamp.
+    pub fn set_additive(&mut self, additive: bool) {
+        self.params.blend = if additive {
+            let add = glium::BlendingFunction::Addition {
+                source: glium::LinearBlendingFactor::SourceAlpha,
+                destination: glium::LinearBlendingFactor::One,
+            };
+            glium::Blend {
+                color: add,
+                alpha: glium::BlendingFunction::Addition {
+                    source: glium::LinearBlendingFactor::One,
+                    destination: glium::LinearBlendingFactor::One,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            }
+        } else {
+            glium::Blend::alpha_blending()
+        };
+    }
+
+    /// Distort everything that is drawn with a fisheye lens, or stop distorting with `None`.
+    ///
+    /// Move the lens with the cursor to magnify the area under it.
+    pub fn set_lens(&mut self, lens: Option<Lens>) {
+        self.lens = lens;
+    }
+
+    /// Get the lens set with `set_lens`.
+    pub fn lens(&self) -> Option<Lens> {
+        self.lens
+    }
+
+    /// Make the glow of nodes, edges, and curves flicker with noise, or stop flickering with `None`.
+    pub fn set_shimmer(&mut self, shimmer: Option<Shimmer>) {
+        self.shimmer = shimmer;
+    }
+
+    /// Clip everything that is drawn to a polygon or texture, or stop clipping with `None`.
+    ///
+    /// The mask lives in the lowest bit of the stencil buffer, so the target must have a stencil buffer. Call
+    /// `write_mask` once per frame before drawing, and again whenever the view or the lens changes mid-frame.
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask.map(|mask| match mask {
+            Mask::Polygon(points) => {
+                // The lens bends straight edges, so they are split into short segments that can follow it.
+                let vertices = points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .flat_map(|(&a, &b)| {
+                        (0..MASK_EDGE_SEGMENTS).map(move |i| {
+                            let t = i as f32 / MASK_EDGE_SEGMENTS as f32;
+                            MaskVertex {
+                                position: [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t],
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                MaskData::Polygon(program::allocate(
+                    glium::VertexBuffer::new(self.display, &vertices),
+                    "the mask polygon",
+                ))
+            }
+            Mask::Texture(texture) => MaskData::Texture(texture),
+        });
+    }
+
     /// Take a series of nodes and draw them in parallel on the GPU.
     pub fn render_nodes<S>(
         &self,
@@ -142,7 +728,30 @@ where
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, nodes).unwrap();
+        self.render_nodes_with_uniforms(target, modelview, projection, nodes, &self.node_program, &[]);
+    }
+
+    /// Draw nodes like `render_nodes` with a custom `program`, adding the `extra` uniforms to the ones the renderer
+    /// sets.
+    ///
+    /// This lets custom shader code receive data that changes every frame, like
+    /// `&[("u_time", UniformValue::Float(t))]`. The program is fed the fields of `Node` as vertex attributes of the
+    /// same names, just like the stock node shaders, and uniforms it doesn't declare are ignored.
+    pub fn render_nodes_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(nodes)) {
+            return;
+        }
+        let vertex_buffer = self.upload(nodes);
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
 
         let uniforms = uniform! {
@@ -150,138 +759,1427 @@ where
             projection: projection,
         };
 
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.node_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
     }
 
-    /// Take a series of lines (edges) and draw them in parallel on the GPU.
+    /// Take a series of nodes and draw them in parallel on the GPU with boundaries computed from exact distances.
     ///
-    /// These will have round ends.
-    pub fn render_edges_round<S>(
+    /// The edges of the inner disc and the glow are softened over exactly one pixel using screen-space derivatives,
+    /// so nodes stay crisp no matter how far in they are zoomed.
+    pub fn render_nodes_sdf<S>(
         &self,
         target: &mut S,
         modelview: [[f32; 3]; 3],
         projection: [[f32; 3]; 3],
-        edges: &[Node],
+        nodes: &[Node],
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, edges).unwrap();
-        let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+        if !self.check(|| validate::validate(nodes)) {
+            return;
+        }
+        let vertex_buffer = self.upload(nodes);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
 
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.round_edge_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.sdf_node_program,
+            &uniforms,
+        );
     }
 
-    /// Take a series of lines (edges) and draw them in parallel on the GPU.
+    /// Draw nodes as discs of their inner color and inner radius using point sprites.
     ///
-    /// These will have flat ends.
-    pub fn render_edges_flat<S>(
+    /// This skips the geometry shader and the glow entirely, which makes it fast enough to draw tens of millions of
+    /// nodes as an overview. Sprites are never smaller than a pixel, and drivers limit how large they can be.
+    pub fn render_sprites<S>(
         &self,
         target: &mut S,
         modelview: [[f32; 3]; 3],
         projection: [[f32; 3]; 3],
-        edges: &[Node],
+        nodes: &[Node],
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, edges).unwrap();
-        let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+        if !self.check(|| validate::validate(nodes)) {
+            return;
+        }
+        let vertex_buffer = self.upload(nodes);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
 
+        // Radii are in view space, so only the projection and the height of the target decide their size in pixels.
+        let (_, height) = target.get_dimensions();
+        let pixels_per_unit = projection[1][0].hypot(projection[1][1]) * 0.5 * height as f32;
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
+            pixels_per_unit: pixels_per_unit,
         };
 
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.flat_edge_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.sprite_program,
+            &uniforms,
+        );
     }
 
-    /// Take a series of triangles (quadratic bezier curves) and draw them in parallel on the GPU.
+    /// Draw nodes as point sprites with `render_sprites` while zoomed out and with their full glow once zoomed in.
+    ///
+    /// The sprites are used while `modelview` scales positions by less than `zoom`, which is where nodes are packed
+    /// too densely for their glow to be told apart anyway.
+    pub fn render_nodes_lod<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: &[Node],
+        zoom: f32,
+    ) where
+        S: Surface,
+    {
+        if modelview[0][0].hypot(modelview[0][1]) < zoom {
+            self.render_sprites(target, modelview, projection, nodes);
+        } else {
+            self.render_nodes(target, modelview, projection, nodes);
+        }
+    }
+
+    /// Take a series of lines (edges) and draw them in parallel on the GPU.
     ///
     /// These will have round ends.
-    pub fn render_qbeziers_round<S>(
+    pub fn render_edges_round<S>(
         &self,
         target: &mut S,
         modelview: [[f32; 3]; 3],
         projection: [[f32; 3]; 3],
-        qbeziers: &[QBezier],
+        edges: &[Node],
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, qbeziers).unwrap();
-        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+        self.render_edges_round_with_uniforms(target, modelview, projection, edges, &self.round_edge_program, &[]);
+    }
+
+    /// Draw round edges like `render_edges_round` with a custom `program` and `extra` uniforms, as
+    /// `render_nodes_with_uniforms` does.
+    pub fn render_edges_round_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate_edges2(edges)) {
+            return;
+        }
+        let vertex_buffer = self.upload(edges);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
 
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.round_qbezier_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
     }
 
-    /// Take a series of triangles (quadratic bezier curves) and draw them in parallel on the GPU.
+    /// Take a series of lines (edges) and draw them in parallel on the GPU.
     ///
     /// These will have flat ends.
-    pub fn render_qbeziers_flat<S>(
+    pub fn render_edges_flat<S>(
         &self,
         target: &mut S,
         modelview: [[f32; 3]; 3],
         projection: [[f32; 3]; 3],
-        qbeziers: &[QBezier],
+        edges: &[Node],
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, qbeziers).unwrap();
-        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+        self.render_edges_flat_with_uniforms(target, modelview, projection, edges, &self.flat_edge_program, &[]);
+    }
+
+    /// Draw flat edges like `render_edges_flat` with a custom `program` and `extra` uniforms, as
+    /// `render_nodes_with_uniforms` does.
+    pub fn render_edges_flat_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate_edges2(edges)) {
+            return;
+        }
+        let vertex_buffer = self.upload(edges);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
 
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.flat_qbezier_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
+    }
+
+    /// Draw round edges like `render_edges_round`, except where they pass under the inner disc of any of `nodes`.
+    ///
+    /// Hubs with hundreds of edges converging on them stay readable instead of drowning in the edge glow. The discs
+    /// are written to the second bit of the stencil buffer before the edges are drawn and cleared from it after, so
+    /// the target must have a stencil buffer that was cleared this frame, such as by `write_mask`. Draw the nodes
+    /// themselves afterwards as usual.
+    pub fn render_edges_round_occluded<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &[Node],
+        nodes: &[Node],
+    ) where
+        S: Surface,
+    {
+        self.render_edges_occluded(target, modelview, projection, edges, nodes, &self.round_edge_program);
+    }
+
+    /// Draw flat edges like `render_edges_flat`, except where they pass under the inner disc of any of `nodes`.
+    ///
+    /// See `render_edges_round_occluded`.
+    pub fn render_edges_flat_occluded<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &[Node],
+        nodes: &[Node],
+    ) where
+        S: Surface,
+    {
+        self.render_edges_occluded(target, modelview, projection, edges, nodes, &self.flat_edge_program);
+    }
+
+    /// Take a series of triangles (quadratic bezier curves) and draw them in parallel on the GPU.
+    ///
+    /// These will have round ends.
+    pub fn render_qbeziers_round<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        qbeziers: &[QBezier],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(qbeziers)) {
+            return;
+        }
+        let vertex_buffer = self.upload(qbeziers);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.round_qbezier_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of triangles (quadratic bezier curves) and draw them in parallel on the GPU.
+    ///
+    /// These will have flat ends.
+    pub fn render_qbeziers_flat<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        qbeziers: &[QBezier],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(qbeziers)) {
+            return;
+        }
+        let vertex_buffer = self.upload(qbeziers);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.flat_qbezier_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of polylines and draw them in parallel on the GPU.
+    ///
+    /// Each polyline is a sequence of nodes whose styles are interpolated along the line.
+    /// Consecutive segments meet with `join` and never overlap, so there are no seams at the joints.
+    pub fn render_polylines<S, P>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        polylines: &[P],
+        join: Join,
+        cap: Cap,
+    ) where
+        S: Surface,
+        P: AsRef<[Node]>,
+    {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for polyline in polylines {
+            let polyline = polyline.as_ref();
+            if !self.check(|| validate::validate_polyline2(polyline)) {
+                continue;
+            }
+            let base = vertices.len() as u32;
+            let len = polyline.len() as u32;
+            // The adjacent vertex of an end segment is its own end point, which the shader draws as a cap.
+            for i in 0..len.saturating_sub(1) {
+                let previous = if i == 0 { i } else { i - 1 };
+                let next = if i + 2 < len { i + 2 } else { i + 1 };
+                indices.extend_from_slice(&[base + previous, base + i, base + i + 1, base + next]);
+            }
+            vertices.extend_from_slice(polyline);
+        }
+        if indices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer = self.upload(&vertices);
+        let index_buffer = program::allocate(
+            glium::IndexBuffer::new(self.display, glium::index::PrimitiveType::LinesListAdjacency, &indices),
+            "an index buffer",
+        );
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+            join: match join {
+                Join::Round => 0i32,
+                Join::Miter => 1i32,
+            },
+            cap: match cap {
+                Cap::Round => 0i32,
+                Cap::Flat => 1i32,
+            },
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            &index_buffer,
+            &self.polyline_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of rounded rectangle nodes and draw them in parallel on the GPU.
+    pub fn render_rects<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        rects: &[RectNode],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(rects)) {
+            return;
+        }
+        let vertex_buffer = self.upload(rects);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.rect_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of pie nodes and draw them in parallel on the GPU.
+    pub fn render_pies<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        pies: &[PieNode],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(pies)) {
+            return;
+        }
+        let vertex_buffer = self.upload(pies);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.pie_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of progress rings and draw them in parallel on the GPU.
+    ///
+    /// Draw the rings after the nodes they decorate.
+    pub fn render_rings<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        rings: &[RingNode],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(rings)) {
+            return;
+        }
+        let vertex_buffer = self.upload(rings);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.ring_program,
+            &uniforms,
+        );
+    }
+
+    /// Take a series of ribbons (bundles of edges) and draw them in parallel on the GPU.
+    ///
+    /// Draw the ribbons before the nodes they connect.
+    pub fn render_ribbons<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        ribbons: &[Ribbon],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(ribbons)) {
+            return;
+        }
+        let vertex_buffer = self.upload(ribbons);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            &vertex_buffer,
+            0..vertex_buffer.len(),
+            indices,
+            &self.ribbon_program,
+            &uniforms,
+        );
+    }
+
+    /// Draw images stretched over rectangles in the same space as the nodes, such as a floor plan or map tiles.
+    ///
+    /// Draw the background before the graph so it goes under it. Each image is drawn with its own draw call, in
+    /// order, and is not distorted by the lens. See `geo::tile_bounds` for the rectangles of slippy map tiles.
+    pub fn render_images<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        images: &[(&glium::texture::Texture2d, Rect)],
+    ) where
+        S: Surface,
+    {
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip);
+        for &(image, ref bounds) in images {
+            let vertices = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]
+                .iter()
+                .map(|&[u, v]| ImageVertex {
+                    position: [
+                        bounds.min[0] + u * (bounds.max[0] - bounds.min[0]),
+                        bounds.min[1] + v * (bounds.max[1] - bounds.min[1]),
+                    ],
+                    tex_coords: [u, v],
+                })
+                .collect::<Vec<_>>();
+            let vertex_buffer = self.upload(&vertices);
+            let uniforms = uniform! {
+                modelview: modelview,
+                projection: projection,
+                image: image
+                    .sampled()
+                    .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                    .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
+            };
+
+            self.draw(
+                target,
+                modelview,
+                projection,
+                &vertex_buffer,
+                0..vertex_buffer.len(),
+                indices,
+                &self.image_program,
+                &uniforms,
+            );
+        }
+    }
+
+    /// Draw the nodes of `tiles` that overlap `view`, which is a rectangle in the same space as their positions.
+    ///
+    /// Tiles that are entirely outside of the view are skipped without looking at their nodes. The glow of the tiles
+    /// is measured with the scale of `modelview` and the lens, so tiles just outside the view still draw their glow.
+    pub fn render_tiled_nodes<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        tiles: &Tiles,
+        view: &Rect,
+    ) where
+        S: Surface,
+    {
+        self.render_tiled(
+            target,
+            modelview,
+            projection,
+            tiles,
+            view,
+            glium::index::PrimitiveType::Points,
+            &self.node_program,
+        );
+    }
+
+    /// Draw the edges of `tiles` that overlap `view` with round ends, like `render_tiled_nodes`.
+    pub fn render_tiled_edges_round<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        tiles: &Tiles,
+        view: &Rect,
+    ) where
+        S: Surface,
+    {
+        self.render_tiled(
+            target,
+            modelview,
+            projection,
+            tiles,
+            view,
+            glium::index::PrimitiveType::LinesList,
+            &self.round_edge_program,
+        );
+    }
+
+    /// Draw the edges of `tiles` that overlap `view` with flat ends, like `render_tiled_nodes`.
+    pub fn render_tiled_edges_flat<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        tiles: &Tiles,
+        view: &Rect,
+    ) where
+        S: Surface,
+    {
+        self.render_tiled(
+            target,
+            modelview,
+            projection,
+            tiles,
+            view,
+            glium::index::PrimitiveType::LinesList,
+            &self.flat_edge_program,
+        );
+    }
+
+    /// Draw every node in a vertex buffer that is kept between frames, such as one from a `BufferCache`.
+    pub fn render_nodes_buffer<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: &glium::VertexBuffer<Node>,
+    ) where
+        S: Surface,
+    {
+        self.render_buffer(
+            target,
+            modelview,
+            projection,
+            nodes,
+            glium::index::PrimitiveType::Points,
+            &self.node_program,
+        );
+    }
+
+    /// Draw every edge (two nodes each) in a vertex buffer that is kept between frames with round ends.
+    pub fn render_edges_round_buffer<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &glium::VertexBuffer<Node>,
+    ) where
+        S: Surface,
+    {
+        self.render_buffer(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.round_edge_program,
+        );
+    }
+
+    /// Draw every edge (two nodes each) in a vertex buffer that is kept between frames with flat ends.
+    pub fn render_edges_flat_buffer<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &glium::VertexBuffer<Node>,
+    ) where
+        S: Surface,
+    {
+        self.render_buffer(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.flat_edge_program,
+        );
+    }
+
+    /// Draw the nodes in a vertex buffer that are visible in `visibility`.
+    ///
+    /// The vertex buffer is kept between frames and only the indices of the visible nodes are uploaded, so nodes can
+    /// be shown and hidden every frame without rebuilding it.
+    pub fn render_nodes_filtered<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: &glium::VertexBuffer<Node>,
+        visibility: &Visibility,
+    ) where
+        S: Surface,
+    {
+        self.render_filtered(
+            target,
+            modelview,
+            projection,
+            nodes,
+            visibility,
+            glium::index::PrimitiveType::Points,
+            1,
+            &self.node_program,
+        );
+    }
+
+    /// Draw the edges (two nodes each) in a vertex buffer that are visible in `visibility` with round ends.
+    ///
+    /// Each bit of `visibility` is for a whole edge, like `render_nodes_filtered`.
+    pub fn render_edges_round_filtered<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &glium::VertexBuffer<Node>,
+        visibility: &Visibility,
+    ) where
+        S: Surface,
+    {
+        self.render_filtered(
+            target,
+            modelview,
+            projection,
+            edges,
+            visibility,
+            glium::index::PrimitiveType::LinesList,
+            2,
+            &self.round_edge_program,
+        );
+    }
+
+    /// Draw the edges (two nodes each) in a vertex buffer that are visible in `visibility` with flat ends.
+    ///
+    /// Each bit of `visibility` is for a whole edge, like `render_nodes_filtered`.
+    pub fn render_edges_flat_filtered<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &glium::VertexBuffer<Node>,
+        visibility: &Visibility,
+    ) where
+        S: Surface,
+    {
+        self.render_filtered(
+            target,
+            modelview,
+            projection,
+            edges,
+            visibility,
+            glium::index::PrimitiveType::LinesList,
+            2,
+            &self.flat_edge_program,
+        );
+    }
+
+    /// Take an iterator of nodes and draw them in parallel on the GPU.
+    ///
+    /// The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_nodes_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            nodes,
+            glium::index::PrimitiveType::Points,
+            &self.node_program,
+        );
+    }
+
+    /// Take an iterator of lines (edges) and draw them in parallel on the GPU.
+    ///
+    /// These will have round ends. The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_edges_round_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.round_edge_program,
+        );
+    }
+
+    /// Take an iterator of lines (edges) and draw them in parallel on the GPU.
+    ///
+    /// These will have flat ends. The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_edges_flat_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.flat_edge_program,
+        );
+    }
+
+    /// Resolve a series of styled nodes against a StyleSheet and draw them in parallel on the GPU.
+    pub fn render_styled_nodes<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        sheet: &StyleSheet,
+        nodes: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let nodes = nodes.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_nodes(target, modelview, projection, &nodes);
+    }
+
+    /// Resolve a series of styled lines (edges) against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These will have round ends.
+    pub fn render_styled_edges_round<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        sheet: &StyleSheet,
+        edges: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let edges = edges.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_edges_round(target, modelview, projection, &edges);
+    }
+
+    /// Resolve a series of styled lines (edges) against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These will have flat ends.
+    pub fn render_styled_edges_flat<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        sheet: &StyleSheet,
+        edges: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let edges = edges.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_edges_flat(target, modelview, projection, &edges);
+    }
+
+    /// Resolve a series of styled quadratic bezier curves against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These will have round ends.
+    pub fn render_styled_qbeziers_round<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        sheet: &StyleSheet,
+        qbeziers: &[StyledQBezier],
+    ) where
+        S: Surface,
+    {
+        let qbeziers = qbeziers.iter().map(|b| b.resolve(sheet)).collect::<Vec<_>>();
+        self.render_qbeziers_round(target, modelview, projection, &qbeziers);
+    }
+
+    /// Resolve a series of styled quadratic bezier curves against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These will have flat ends.
+    pub fn render_styled_qbeziers_flat<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        sheet: &StyleSheet,
+        qbeziers: &[StyledQBezier],
+    ) where
+        S: Surface,
+    {
+        let qbeziers = qbeziers.iter().map(|b| b.resolve(sheet)).collect::<Vec<_>>();
+        self.render_qbeziers_flat(target, modelview, projection, &qbeziers);
+    }
+
+    /// Copy elements into a new vertex buffer, which is slow when done every frame.
+    fn upload<T>(&self, elements: &[T]) -> glium::VertexBuffer<T>
+    where
+        T: Copy + glium::Vertex,
+    {
+        if self.warned_upload.replace(true) {
+            trace!("allocating a vertex buffer for {} elements", elements.len());
+        } else {
+            warn!(
+                "allocating a new vertex buffer on every draw, keep one with a BufferCache and draw it with the \
+                 render_*_buffer methods instead"
+            );
+        }
+        program::allocate(glium::VertexBuffer::new(self.display, elements), "a vertex buffer")
+    }
+
+    /// Tell whether the elements are fine to draw, which they are unless validation is enabled and finds problems.
+    fn check<F>(&self, errors: F) -> bool
+    where
+        F: FnOnce() -> Vec<ValidationError>,
+    {
+        !self.validate || validate::report(&errors())
+    }
+
+    fn render_edges_occluded<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        edges: &[Node],
+        nodes: &[Node],
+        program: &glium::Program,
+    ) where
+        S: Surface,
+    {
+        // Both are checked so that every problem is reported.
+        let valid = self.check(|| validate::validate_edges2(edges));
+        if !(self.check(|| validate::validate(nodes)) && valid) {
+            return;
+        }
+        let edge_buffer = self.upload(edges);
+        let node_buffer = self.upload(nodes);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw_occluded(
+            target,
+            modelview,
+            projection,
+            &edge_buffer,
+            0..edge_buffer.len(),
+            indices,
+            program,
+            &uniforms,
+            Some(&node_buffer),
+        );
+    }
+
+    fn render_buffer<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        vertices: &glium::VertexBuffer<Node>,
+        primitive: glium::index::PrimitiveType,
+        program: &glium::Program,
+    ) where
+        S: Surface,
+    {
+        let indices = glium::index::NoIndices(primitive);
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            vertices,
+            0..vertices.len(),
+            indices,
+            program,
+            &uniforms,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_filtered<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        vertices: &glium::VertexBuffer<Node>,
+        visibility: &Visibility,
+        primitive: glium::index::PrimitiveType,
+        per_element: usize,
+        program: &glium::Program,
+    ) where
+        S: Surface,
+    {
+        if visibility.len() * per_element > vertices.len() {
+            warn!(
+                "visibility has {} elements but the vertex buffer only holds {}",
+                visibility.len(),
+                vertices.len() / per_element
+            );
+        }
+        // Elements that don't entirely fit in the buffer are skipped.
+        let end = vertices.len() / per_element * per_element;
+        let mut indices = visibility.indices(per_element);
+        indices.retain(|&i| (i as usize) < end);
+        if indices.is_empty() {
+            return;
+        }
+        let indices = program::allocate(glium::IndexBuffer::new(self.display, primitive, &indices), "an index buffer");
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            vertices,
+            0..vertices.len(),
+            &indices,
+            program,
+            &uniforms,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_tiled<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        tiles: &Tiles,
+        view: &Rect,
+        primitive: glium::index::PrimitiveType,
+        program: &glium::Program,
+    ) where
+        S: Surface,
+    {
+        let indices = glium::index::NoIndices(primitive);
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        let mut scale = modelview[0][0].hypot(modelview[0][1]);
+        let mut view = *view;
+        if let Some(lens) = self.lens.filter(|lens| lens.strength > 0.0 && lens.radius > 0.0) {
+            // The lens can pull anything under it to anywhere else under it and magnifies radii by up to
+            // `strength + 1`, so everything under the lens is drawn if any of it is in view.
+            if let Some(inverse) = Matrix3::from(modelview).invert() {
+                let center = inverse * Vector3::new(lens.center[0], lens.center[1], 1.0);
+                let lensed = Rect {
+                    min: [center.x, center.y],
+                    max: [center.x, center.y],
+                }.expand(lens.radius / scale);
+                if lensed.intersects(&view) {
+                    view = view.union(&lensed);
+                }
+            }
+            scale /= lens.strength + 1.0;
+        }
+
+        for range in tiles.visible(&view, scale) {
+            self.draw(
+                target,
+                modelview,
+                projection,
+                tiles.buffer(),
+                range,
+                indices,
+                program,
+                &uniforms,
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw<'b, S, T, I, U>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        vertices: &'b glium::VertexBuffer<T>,
+        range: Range<usize>,
+        indices: I,
+        program: &glium::Program,
+        uniforms: &U,
+    ) where
+        S: Surface,
+        T: Copy,
+        I: Into<glium::index::IndicesSource<'b>> + Copy,
+        U: glium::uniforms::Uniforms,
+    {
+        self.draw_occluded(target, modelview, projection, vertices, range, indices, program, uniforms, None);
+    }
+
+    /// Draw like `draw`, except where the inner discs of the `occluders` cover the target.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_occluded<'b, S, T, I, U>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        vertices: &'b glium::VertexBuffer<T>,
+        range: Range<usize>,
+        indices: I,
+        program: &glium::Program,
+        uniforms: &U,
+        occluders: Option<&glium::VertexBuffer<Node>>,
+    ) where
+        S: Surface,
+        T: Copy,
+        I: Into<glium::index::IndicesSource<'b>> + Copy,
+        U: glium::uniforms::Uniforms,
+    {
+        let mut params = self.params.clone();
+        // The bits of the stencil that are tested and the values they must have for drawing to pass.
+        let mut bits = 0;
+        let mut reference = 0;
+        if self.mask.is_some() {
+            bits |= 1;
+            reference |= 1;
+        }
+        if let Some(occluders) = occluders {
+            self.write_occluders(target, modelview, projection, occluders, true);
+            bits |= 2;
+        }
+        if bits != 0 {
+            let test = glium::StencilTest::IfEqual { mask: bits };
+            params.stencil = glium::draw_parameters::Stencil {
+                test_clockwise: test,
+                reference_value_clockwise: reference,
+                test_counter_clockwise: test,
+                reference_value_counter_clockwise: reference,
+                ..Default::default()
+            };
+        }
+        let shimmer = self.shimmer.unwrap_or(Shimmer {
+            amount: 0.0,
+            ..Default::default()
+        });
+        let shimmer = [
+            ("shimmer_time", UniformValue::Float(shimmer.time)),
+            ("shimmer_amount", UniformValue::Float(shimmer.amount)),
+            ("shimmer_speed", UniformValue::Float(shimmer.speed)),
+            ("shimmer_scale", UniformValue::Float(shimmer.scale)),
+        ];
+        let lens = self.lens_uniforms();
+        let uniforms = ExtraUniforms {
+            uniforms: &ExtraUniforms {
+                uniforms: uniforms,
+                extra: &lens,
+            },
+            extra: &shimmer,
+        };
+        for (view, params) in self.debug.passes(&params) {
+            let result = target.draw(
+                vertices.slice(range.clone()).unwrap(),
+                indices,
+                program,
+                &DebugUniforms {
+                    uniforms: &uniforms,
+                    view: view,
+                },
+                &params,
+            );
+            if let Err(e) = result {
+                error!("draw call failed: {}", e);
+            }
+        }
+        if let Some(occluders) = occluders {
+            // The mask stays in the stencil for the rest of the frame, so only the occluders are taken back out.
+            self.write_occluders(target, modelview, projection, occluders, false);
+        }
+    }
+
+    /// The uniforms of the `lens` function that every lensed vertex shader has, which turn it off without a lens.
+    fn lens_uniforms(&self) -> [(&'static str, UniformValue<'static>); 3] {
+        let lens = self.lens.unwrap_or_default();
+        [
+            ("lens_center", UniformValue::Vec2(lens.center)),
+            ("lens_radius", UniformValue::Float(lens.radius)),
+            ("lens_strength", UniformValue::Float(lens.strength)),
+        ]
+    }
+
+    /// Clear the stencil buffer of `target` and set its lowest bit wherever the mask lets drawing through.
+    ///
+    /// Call this once per frame before drawing with a mask, since the stencil buffer does not survive swapping
+    /// buffers. A polygon mask is placed with `modelview` and the current lens, so call it again if either changes
+    /// before the frame is done. Without a mask this only clears the stencil buffer.
+    pub fn write_mask<S>(&self, target: &mut S, modelview: [[f32; 3]; 3], projection: [[f32; 3]; 3])
+    where
+        S: Surface,
+    {
+        target.clear_stencil(0);
+        let mask = match self.mask {
+            Some(ref mask) => mask,
+            None => return,
+        };
+        let operation = match *mask {
+            // Inverting the stencil for every triangle of a fan leaves exactly the inside of the polygon set,
+            // even when it is concave or self-intersecting.
+            MaskData::Polygon(_) => glium::StencilOperation::Invert,
+            MaskData::Texture(_) => glium::StencilOperation::Replace,
+        };
+        let params = glium::DrawParameters {
+            color_mask: (false, false, false, false),
+            stencil: glium::draw_parameters::Stencil {
+                test_clockwise: glium::StencilTest::AlwaysPass,
+                reference_value_clockwise: 1,
+                write_mask_clockwise: 1,
+                depth_pass_operation_clockwise: operation,
+                test_counter_clockwise: glium::StencilTest::AlwaysPass,
+                reference_value_counter_clockwise: 1,
+                write_mask_counter_clockwise: 1,
+                depth_pass_operation_counter_clockwise: operation,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = match *mask {
+            MaskData::Polygon(ref vertices) => target.draw(
+                vertices,
+                glium::index::NoIndices(glium::index::PrimitiveType::TriangleFan),
+                &self.mask_program,
+                &ExtraUniforms {
+                    uniforms: &uniform! {
+                        modelview: modelview,
+                        projection: projection,
+                    },
+                    extra: &self.lens_uniforms(),
+                },
+                &params,
+            ),
+            MaskData::Texture(ref texture) => target.draw(
+                &self.fullscreen,
+                glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+                &self.mask_texture_program,
+                &uniform! {
+                    modelview: IDENTITY,
+                    projection: IDENTITY,
+                    mask: texture,
+                },
+                &params,
+            ),
+        };
+        if let Err(e) = result {
+            error!("failed to draw the mask: {}", e);
+        }
+    }
+
+    /// Set or clear the second bit of the stencil buffer under the inner disc of every occluding node.
+    fn write_occluders<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        occluders: &glium::VertexBuffer<Node>,
+        set: bool,
+    ) where
+        S: Surface,
+    {
+        let operation = if set {
+            glium::StencilOperation::Replace
+        } else {
+            glium::StencilOperation::Zero
+        };
+        let params = glium::DrawParameters {
+            color_mask: (false, false, false, false),
+            stencil: glium::draw_parameters::Stencil {
+                test_clockwise: glium::StencilTest::AlwaysPass,
+                reference_value_clockwise: 2,
+                write_mask_clockwise: 2,
+                depth_pass_operation_clockwise: operation,
+                test_counter_clockwise: glium::StencilTest::AlwaysPass,
+                reference_value_counter_clockwise: 2,
+                write_mask_counter_clockwise: 2,
+                depth_pass_operation_counter_clockwise: operation,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = target.draw(
+            occluders,
+            glium::index::NoIndices(glium::index::PrimitiveType::Points),
+            &self.occluder_program,
+            &ExtraUniforms {
+                uniforms: &uniform! {
+                    modelview: modelview,
+                    projection: projection,
+                },
+                extra: &self.lens_uniforms(),
+            },
+            &params,
+        );
+        if let Err(e) = result {
+            error!("failed to draw the occluding nodes: {}", e);
+        }
+    }
+
+    fn draw_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        nodes: I,
+        primitive: glium::index::PrimitiveType,
+        program: &glium::Program,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        let nodes = nodes.into_iter();
+        let len = nodes.len();
+        if len == 0 {
+            return;
+        }
+
+        let mut scratch = self.scratch.borrow_mut();
+        if scratch.as_ref().map(|b| b.len() < len).unwrap_or(true) {
+            debug!("growing the reusable vertex buffer to {} elements", len.next_power_of_two());
+            *scratch = Some(program::allocate(
+                glium::VertexBuffer::empty_dynamic(self.display, len.next_power_of_two()),
+                "the reusable vertex buffer",
+            ));
+        }
+        let vertex_buffer = scratch.as_mut().unwrap();
+        {
+            let mut mapping = vertex_buffer.map_write();
+            let mut errors = Vec::new();
+            // Edges are validated in pairs to find those with zero length.
+            let mut ends = Vec::new();
+            let pairs = self.validate && primitive == glium::index::PrimitiveType::LinesList;
+            for (i, node) in nodes.enumerate().take(len) {
+                let node = node.glow_node();
+                if self.validate {
+                    node.validate(i, &mut errors);
+                }
+                if pairs {
+                    ends.push(node.position);
+                }
+                mapping.set(i, node);
+            }
+            validate::zero_length(ends.chunks(2).map(|e| e.iter()), 2, &mut errors);
+            if !validate::report(&errors) {
+                return;
+            }
+        }
+        let indices = glium::index::NoIndices(primitive);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(
+            target,
+            modelview,
+            projection,
+            vertex_buffer,
+            0..len,
+            indices,
+            program,
+            &uniforms,
+        );
     }
 }
@@ -14,10 +14,12 @@ out float ginner_radius;
 uniform mat3 modelview;
 
 void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
     ginner_color = inner_color;
     gfalloff_color = falloff_color;
     gfalloff = falloff;
-    gfalloff_radius = falloff_radius;
-    ginner_radius = inner_radius;
-    gl_Position = vec4((modelview * vec3(position, 1.0)).xy, 0.0, 1.0);
+    gfalloff_radius = falloff_radius * magnification;
+    ginner_radius = inner_radius * magnification;
+    gl_Position = vec4(view_position, 0.0, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,37 @@
+#version 150
+
+in vec2 delta;
+in vec4 finner_color;
+in vec4 ffalloff_color;
+in float finner_radius;
+in float ffalloff_radius;
+in float ffalloff;
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    // Exact distance from the center of the node, recomputed for every fragment.
+    float dist = length(delta);
+    // The change in distance across one pixel, used to soften boundaries by exactly one pixel at any zoom.
+    float aa = max(fwidth(dist), 0.0000001);
+    float full_radius = finner_radius + ffalloff_radius;
+
+    float travel = clamp(dist / finner_radius, 0.0, 1.0);
+    // Manually interpolate the inner color into the falloff color.
+    vec4 inner = finner_color * (1.0 - travel) + ffalloff_color * travel;
+
+    float outer_travel = clamp((dist - finner_radius) / ffalloff_radius, 0.0, 1.0);
+    vec4 outer = vec4(ffalloff_color.xyz, ffalloff_color.a * max(0.0, 1.0 - pow(outer_travel, ffalloff)));
+
+    color = mix(inner, outer, smoothstep(finner_radius - 0.5 * aa, finner_radius + 0.5 * aa, dist));
+    color.a *= 1.0 - smoothstep(full_radius - 0.5 * aa, full_radius + 0.5 * aa, dist);
+    color.a *= shimmer();
+}
@@ -0,0 +1,17 @@
+#version 150
+
+in vec2 delta;
+in vec4 finner_color;
+in vec4 ffalloff_color;
+in float finner_radius;
+in float ffalloff_radius;
+in float ffalloff;
+out vec4 color;
+
+void main() {
+    // Only the stencil is written, and only under the inner disc so edges still reach the edge of the glow.
+    if (length(delta) > finner_radius) {
+        discard;
+    }
+    color = vec4(1.0);
+}
@@ -0,0 +1,52 @@
+#version 150
+
+in vec2 delta;
+flat in vec4 fbounds;
+flat in vec4 fcolor0;
+flat in vec4 fcolor1;
+flat in vec4 fcolor2;
+flat in vec4 fcolor3;
+flat in float fhole;
+flat in float ffalloff;
+flat in vec4 ffalloff_color;
+flat in float ffalloff_radius;
+flat in float finner_radius;
+uniform int debug_view;
+out vec4 color;
+
+#define PI 3.14159265358979
+
+void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    float dist = length(delta);
+    // The change in distance across one pixel, used to soften boundaries by exactly one pixel at any zoom.
+    float aa = max(fwidth(dist), 0.0000001);
+
+    // Segments go clockwise starting from the top.
+    float turn = fract(atan(delta.x, delta.y) / (2.0 * PI) + 1.0);
+    vec4 segment;
+    if (turn < fbounds.x) {
+        segment = fcolor0;
+    } else if (turn < fbounds.y) {
+        segment = fcolor1;
+    } else if (turn < fbounds.z) {
+        segment = fcolor2;
+    } else {
+        segment = fcolor3;
+    }
+    // Cut out the hole of a donut.
+    if (fhole > 0.0) {
+        segment.a *= smoothstep(fhole - 0.5 * aa, fhole + 0.5 * aa, dist);
+    }
+
+    float outer_travel = clamp((dist - finner_radius) / ffalloff_radius, 0.0, 1.0);
+    vec4 outer = vec4(ffalloff_color.xyz, ffalloff_color.a * max(0.0, 1.0 - pow(outer_travel, ffalloff)));
+    color = mix(segment, outer, smoothstep(finner_radius - 0.5 * aa, finner_radius + 0.5 * aa, dist));
+}
@@ -0,0 +1,40 @@
+#version 150
+
+in vec2 position;
+in vec4 shares;
+in vec4 color0;
+in vec4 color1;
+in vec4 color2;
+in vec4 color3;
+in float hole;
+in float falloff;
+in vec4 falloff_color;
+in float falloff_radius;
+in float inner_radius;
+out vec4 gshares;
+out vec4 gcolor0;
+out vec4 gcolor1;
+out vec4 gcolor2;
+out vec4 gcolor3;
+out float ghole;
+out float gfalloff;
+out vec4 gfalloff_color;
+out float gfalloff_radius;
+out float ginner_radius;
+uniform mat3 modelview;
+
+void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
+    gshares = shares;
+    gcolor0 = color0;
+    gcolor1 = color1;
+    gcolor2 = color2;
+    gcolor3 = color3;
+    ghole = hole;
+    gfalloff = falloff;
+    gfalloff_color = falloff_color;
+    gfalloff_radius = falloff_radius * magnification;
+    ginner_radius = inner_radius * magnification;
+    gl_Position = vec4(view_position, 0.0, 1.0);
+}
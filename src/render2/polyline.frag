@@ -0,0 +1,85 @@
+#version 150
+
+in vec2 realpos;
+flat in vec2 fstart;
+flat in vec2 fend;
+flat in vec2 fstart_normal;
+flat in vec2 fend_normal;
+flat in vec4 finner_color0;
+flat in vec4 finner_color1;
+flat in vec4 ffalloff_color0;
+flat in vec4 ffalloff_color1;
+flat in float ffalloff0;
+flat in float ffalloff1;
+flat in float ffalloff_radius0;
+flat in float ffalloff_radius1;
+flat in float finner_radius0;
+flat in float finner_radius1;
+uniform int join;
+uniform int cap;
+
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    bool start_join = fstart_normal != vec2(0.0);
+    bool end_join = fend_normal != vec2(0.0);
+
+    // Each fragment near a join belongs to exactly one of the two segments, so joins have no seams.
+    if (start_join && dot(realpos - fstart, fstart_normal) < 0.0) {
+        discard;
+    }
+    if (end_join && dot(realpos - fend, fend_normal) > 0.0) {
+        discard;
+    }
+
+    float len = distance(fstart, fend);
+    vec2 dir = (fend - fstart) / len;
+    float along = dot(realpos - fstart, dir);
+    float across = abs(dot(realpos - fstart, vec2(-dir.y, dir.x)));
+
+    float dist;
+    if (along < 0.0) {
+        if (start_join) {
+            dist = join == 0 ? distance(realpos, fstart) : across;
+        } else if (cap == 0) {
+            dist = distance(realpos, fstart);
+        } else {
+            discard;
+        }
+    } else if (along > len) {
+        if (end_join) {
+            dist = join == 0 ? distance(realpos, fend) : across;
+        } else if (cap == 0) {
+            dist = distance(realpos, fend);
+        } else {
+            discard;
+        }
+    } else {
+        dist = across;
+    }
+
+    float t = clamp(along / len, 0.0, 1.0);
+    vec4 inner_color = mix(finner_color0, finner_color1, t);
+    vec4 falloff_color = mix(ffalloff_color0, ffalloff_color1, t);
+    float falloff = mix(ffalloff0, ffalloff1, t);
+    float falloff_radius = mix(ffalloff_radius0, ffalloff_radius1, t);
+    float inner_radius = mix(finner_radius0, finner_radius1, t);
+
+    if (dist <= inner_radius) {
+        float travel = dist / inner_radius;
+        // Manually interpolate the inner color into the falloff color.
+        color = inner_color * (1.0 - travel) + falloff_color * travel;
+    } else {
+        color = vec4(falloff_color.xyz,
+            falloff_color.a * max(0.0, 1.0 - pow((dist - inner_radius) / falloff_radius, falloff)));
+    }
+}
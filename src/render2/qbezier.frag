@@ -15,6 +15,7 @@ flat in float finner_radius0;
 flat in float finner_radius1;
 in vec2 realpos;
 
+uniform int debug_view;
 out vec4 color;
 
 float det(vec2 a, vec2 b) { return a.x * b.y - b.x * a.y; }
@@ -128,6 +129,14 @@ float calculateDistanceToQuadraticBezier(vec2 p, vec2 p0, vec2 p1, vec2 p2, out
 }
 
 void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
     float best_t;
     float best_distance = calculateDistanceToQuadraticBezier(realpos, fposition0, fposition1, fposition2, best_t);
 
@@ -145,4 +154,5 @@ void main() {
         color = vec4(falloff_color.xyz,
             falloff_color.a * max(0.0, 1.0 - pow((best_distance - inner_radius) / falloff_radius, falloff)));
     }
-}
\ No newline at end of file
+    color.a *= shimmer();
+}
@@ -79,4 +79,14 @@ void main() {
         ginner_radius0 = inner_radius1;
         ginner_radius1 = inner_radius0;
     }
+    float magnification0;
+    float magnification1;
+    float magnification2;
+    gposition0 = lens(gposition0, magnification0);
+    gposition1 = lens(gposition1, magnification1);
+    gposition2 = lens(gposition2, magnification2);
+    ginner_radius0 *= magnification0;
+    gfalloff_radius0 *= magnification0;
+    ginner_radius1 *= magnification2;
+    gfalloff_radius1 *= magnification2;
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+#version 150
+
+in vec2 delta;
+flat in vec2 fhalf_size;
+flat in float fcorner_radius;
+flat in vec4 finner_color;
+flat in vec4 ffalloff_color;
+flat in float ffalloff;
+flat in float ffalloff_radius;
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    // Signed distance to the rounded rectangle, which is negative inside of it.
+    vec2 q = abs(delta) - fhalf_size + vec2(fcorner_radius);
+    float dist = length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - fcorner_radius;
+    // The change in distance across one pixel, used to soften the boundary by exactly one pixel at any zoom.
+    float aa = max(fwidth(dist), 0.0000001);
+
+    float outer_travel = clamp(dist / ffalloff_radius, 0.0, 1.0);
+    vec4 outer = vec4(ffalloff_color.xyz, ffalloff_color.a * max(0.0, 1.0 - pow(outer_travel, ffalloff)));
+    color = mix(finner_color, outer, smoothstep(-0.5 * aa, 0.5 * aa, dist));
+}
@@ -0,0 +1,28 @@
+#version 150
+
+in vec2 position;
+in vec2 half_size;
+in float corner_radius;
+in vec4 inner_color;
+in vec4 falloff_color;
+in float falloff;
+in float falloff_radius;
+out vec2 ghalf_size;
+out float gcorner_radius;
+out vec4 ginner_color;
+out vec4 gfalloff_color;
+out float gfalloff;
+out float gfalloff_radius;
+uniform mat3 modelview;
+
+void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
+    ghalf_size = half_size * magnification;
+    gcorner_radius = corner_radius * magnification;
+    ginner_color = inner_color;
+    gfalloff_color = falloff_color;
+    gfalloff = falloff;
+    gfalloff_radius = falloff_radius * magnification;
+    gl_Position = vec4(view_position, 0.0, 1.0);
+}
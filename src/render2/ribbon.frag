@@ -0,0 +1,30 @@
+#version 150
+
+in float across;
+in float fhalf_width;
+in vec4 fcolor;
+flat in float ffill;
+flat in float ffalloff_radius;
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the emitted triangles instead of the band.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    float distance = abs(across);
+    if (distance <= fhalf_width) {
+        // The band is translucent in the middle and brightens towards its edges.
+        float travel = distance / fhalf_width;
+        color = vec4(fcolor.rgb, fcolor.a * mix(ffill, 1.0, travel * travel * travel * travel));
+    } else {
+        // The edges glow outwards.
+        float travel = clamp((distance - fhalf_width) / ffalloff_radius, 0.0, 1.0);
+        color = vec4(fcolor.rgb, fcolor.a * (1.0 - travel) * (1.0 - travel));
+    }
+}
@@ -0,0 +1,36 @@
+#version 150
+
+in vec2 position0;
+in vec2 position1;
+in vec2 position2;
+in float width0;
+in float width1;
+in vec4 color0;
+in vec4 color1;
+in float fill;
+in float falloff_radius;
+out vec2 gposition0;
+out vec2 gposition1;
+out vec2 gposition2;
+out float gwidth0;
+out float gwidth1;
+out vec4 gcolor0;
+out vec4 gcolor1;
+out float gfill;
+out float gfalloff_radius;
+uniform mat3 modelview;
+
+void main() {
+    float magnification0;
+    float magnification1;
+    float magnification2;
+    gposition0 = lens((modelview * vec3(position0, 1.0)).xy, magnification0);
+    gposition1 = lens((modelview * vec3(position1, 1.0)).xy, magnification1);
+    gposition2 = lens((modelview * vec3(position2, 1.0)).xy, magnification2);
+    gwidth0 = width0 * magnification0;
+    gwidth1 = width1 * magnification2;
+    gcolor0 = color0;
+    gcolor1 = color1;
+    gfill = fill;
+    gfalloff_radius = falloff_radius * max(magnification0, magnification2);
+}
@@ -0,0 +1,53 @@
+#version 150
+
+in vec2 delta;
+flat in float fradius;
+flat in float fhalf_width;
+flat in float fprogress;
+flat in vec4 fcolor;
+flat in vec4 ftrack_color;
+flat in float ffalloff;
+flat in vec4 ffalloff_color;
+flat in float ffalloff_radius;
+uniform int debug_view;
+out vec4 color;
+
+#define PI 3.14159265358979
+
+void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    float ring_dist = abs(length(delta) - fradius);
+    // The change in distance across one pixel, used to soften boundaries by exactly one pixel at any zoom.
+    float aa = max(fwidth(ring_dist), 0.0000001);
+
+    // The arc goes clockwise from the top and has round ends.
+    float turn = fract(atan(delta.x, delta.y) / (2.0 * PI) + 1.0);
+    float dist;
+    if (fprogress <= 0.0) {
+        dist = ffalloff_radius + fhalf_width + aa;
+    } else if (turn <= fprogress) {
+        dist = ring_dist;
+    } else {
+        float angle = 2.0 * PI * fprogress;
+        vec2 start = vec2(0.0, fradius);
+        vec2 end = fradius * vec2(sin(angle), cos(angle));
+        dist = min(distance(delta, start), distance(delta, end));
+    }
+
+    float outer_travel = clamp((dist - fhalf_width) / ffalloff_radius, 0.0, 1.0);
+    vec4 outer = vec4(ffalloff_color.xyz, ffalloff_color.a * max(0.0, 1.0 - pow(outer_travel, ffalloff)));
+    vec4 arc = mix(fcolor, outer, smoothstep(fhalf_width - 0.5 * aa, fhalf_width + 0.5 * aa, dist));
+
+    // The remaining part of the ring is drawn as a track under the arc.
+    vec4 track = ftrack_color;
+    track.a *= 1.0 - smoothstep(fhalf_width - 0.5 * aa, fhalf_width + 0.5 * aa, ring_dist);
+    color.a = arc.a + track.a * (1.0 - arc.a);
+    color.rgb = (arc.rgb * arc.a + track.rgb * track.a * (1.0 - arc.a)) / max(color.a, 0.0000001);
+}
@@ -0,0 +1,34 @@
+#version 150
+
+in vec2 position;
+in float radius;
+in float width;
+in float progress;
+in vec4 color;
+in vec4 track_color;
+in float falloff;
+in vec4 falloff_color;
+in float falloff_radius;
+out float gradius;
+out float gwidth;
+out float gprogress;
+out vec4 gcolor;
+out vec4 gtrack_color;
+out float gfalloff;
+out vec4 gfalloff_color;
+out float gfalloff_radius;
+uniform mat3 modelview;
+
+void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
+    gradius = radius * magnification;
+    gwidth = width * magnification;
+    gprogress = progress;
+    gcolor = color;
+    gtrack_color = track_color;
+    gfalloff = falloff;
+    gfalloff_color = falloff_color;
+    gfalloff_radius = falloff_radius * magnification;
+    gl_Position = vec4(view_position, 0.0, 1.0);
+}
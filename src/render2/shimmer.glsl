@@ -0,0 +1,28 @@
+uniform float shimmer_time;
+uniform float shimmer_amount;
+uniform float shimmer_speed;
+uniform float shimmer_scale;
+
+float hash(vec3 p) {
+    return fract(sin(dot(p, vec3(127.1, 311.7, 74.7))) * 43758.5453);
+}
+
+// Value noise from 0 to 1, smoothly interpolated between random values at the corners of a lattice.
+float noise(vec3 p) {
+    vec3 i = floor(p);
+    vec3 f = fract(p);
+    f = f * f * (3.0 - 2.0 * f);
+    float near = mix(mix(hash(i), hash(i + vec3(1.0, 0.0, 0.0)), f.x),
+        mix(hash(i + vec3(0.0, 1.0, 0.0)), hash(i + vec3(1.0, 1.0, 0.0)), f.x), f.y);
+    float far = mix(mix(hash(i + vec3(0.0, 0.0, 1.0)), hash(i + vec3(1.0, 0.0, 1.0)), f.x),
+        mix(hash(i + vec3(0.0, 1.0, 1.0)), hash(i + vec3(1.0, 1.0, 1.0)), f.x), f.y);
+    return mix(near, far, f.z);
+}
+
+// The factor the alpha is scaled by to make the glow flicker, which is 1 when shimmer is off.
+float shimmer() {
+    if (shimmer_amount <= 0.0) {
+        return 1.0;
+    }
+    return 1.0 - shimmer_amount * noise(vec3(gl_FragCoord.xy / shimmer_scale, shimmer_time * shimmer_speed));
+}
@@ -0,0 +1,21 @@
+#version 150
+
+in vec4 finner_color;
+uniform int debug_view;
+out vec4 color;
+
+void main() {
+    // The debug views show the whole square of each sprite.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
+    // Cut the square sprite down to a disc.
+    if (length(gl_PointCoord - vec2(0.5)) > 0.5) {
+        discard;
+    }
+    color = finner_color;
+}
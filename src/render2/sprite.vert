@@ -0,0 +1,18 @@
+#version 150
+
+in vec2 position;
+in vec4 inner_color;
+in float inner_radius;
+out vec4 finner_color;
+uniform mat3 modelview;
+uniform mat3 projection;
+uniform float pixels_per_unit;
+
+void main() {
+    float magnification;
+    vec2 view_position = lens((modelview * vec3(position, 1.0)).xy, magnification);
+    finner_color = inner_color;
+    // Never shrink below a pixel, so that every node in an overview stays visible.
+    gl_PointSize = max(2.0 * inner_radius * magnification * pixels_per_unit, 1.0);
+    gl_Position = vec4((projection * vec3(view_position, 1.0)).xy, 0.0, 1.0);
+}
@@ -1,5 +1,13 @@
+use std::cell::{Cell, RefCell};
+
+use glium::uniforms::UniformValue;
 use glium::{self, Surface};
-use cgmath;
+use cgmath::{self, Matrix4, Vector3};
+use debug::{DebugUniforms, DebugView};
+use uniforms::ExtraUniforms;
+use program;
+use validate::{self, Validate, ValidationError};
+use style::StyleSheet;
 
 /// Node is used to pass nodes into the renderer.
 #[derive(Copy, Clone, Debug)]
@@ -37,7 +45,101 @@ implement_vertex!(
     inner_radius
 );
 
+/// StyledNode is a node that gets its appearance from a named style in a StyleSheet.
+#[derive(Clone, Debug)]
+pub struct StyledNode {
+    pub position: [f32; 3],
+    pub style: String,
+}
+
+impl StyledNode {
+    /// Resolve the style of this node against a StyleSheet to get a Node ready for rendering.
+    pub fn resolve(&self, sheet: &StyleSheet) -> Node {
+        sheet.resolve(&self.style).node3(self.position)
+    }
+}
+
+/// GlowNode lets application types be rendered as nodes without building an intermediate `Vec<Node>`.
+///
+/// Nodes are converted while they are written into a vertex buffer that the Renderer reuses between frames.
+pub trait GlowNode {
+    fn glow_node(&self) -> Node;
+}
+
+impl GlowNode for Node {
+    fn glow_node(&self) -> Node {
+        *self
+    }
+}
+
+impl<T> GlowNode for &T
+where
+    T: GlowNode,
+{
+    fn glow_node(&self) -> Node {
+        (*self).glow_node()
+    }
+}
+
+/// How the two eyes of a stereo image are combined, see `Renderer::render_stereo`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StereoMode {
+    /// Overlay the eyes for red (left) and cyan (right) glasses.
+    Anaglyph,
+    /// Draw the left eye on the left half of the target and the right eye on the right half.
+    ///
+    /// Each half keeps the aspect ratio of the projection, so it sees half as much horizontally.
+    SideBySide,
+}
+
+/// The parameters of a stereo image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stereo {
+    pub mode: StereoMode,
+    /// The distance between the eyes in view space units.
+    pub eye_separation: f32,
+    /// The distance in front of the camera where both eyes see the same image, which appears at screen depth.
+    /// Anything closer pops out of the screen.
+    pub convergence: f32,
+}
+
+impl Default for Stereo {
+    fn default() -> Stereo {
+        Stereo {
+            mode: StereoMode::Anaglyph,
+            eye_separation: 0.065,
+            convergence: 2.0,
+        }
+    }
+}
+
+impl Stereo {
+    /// Get the modelview and projection matrices of the left (`-1.0`) or right (`1.0`) eye.
+    fn eye(
+        &self,
+        side: f32,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+    ) -> ([[f32; 4]; 4], [[f32; 4]; 4]) {
+        let half = 0.5 * self.eye_separation;
+        // Moving the eye to the side moves the world the other way.
+        let offset = Matrix4::from_translation(Vector3::new(-side * half, 0.0, 0.0));
+        let modelview = offset * Matrix4::from(modelview);
+        // Shift the image of each eye so that the parallax is zero at the convergence distance.
+        let mut shift = Matrix4::from_scale(1.0);
+        shift.w.x = side * half * projection[0][0] / self.convergence;
+        let mut projection = shift * Matrix4::from(projection);
+        if self.mode == StereoMode::SideBySide {
+            projection = Matrix4::from_nonuniform_scale(2.0, 1.0, 1.0) * projection;
+        }
+        (modelview.into(), projection.into())
+    }
+}
+
 /// A Renderer is tied to the lifetime of the glium Display and making one builds a GLSL program internally.
+///
+/// Draw calls that fail are logged and skipped, so one bad frame doesn't take down the application. Failing to build
+/// a program or to allocate a buffer is logged and then panics, since nothing can be drawn without them.
 pub struct Renderer<'a, D>
 where
     D: 'a,
@@ -46,7 +148,12 @@ where
     node_program: glium::Program,
     round_edge_program: glium::Program,
     flat_edge_program: glium::Program,
+    sdf_node_program: glium::Program,
     params: glium::DrawParameters<'a>,
+    scratch: RefCell<Option<glium::VertexBuffer<Node>>>,
+    debug: DebugView,
+    validate: bool,
+    warned_upload: Cell<bool>,
 }
 
 impl<'a, D> Renderer<'a, D>
@@ -57,31 +164,120 @@ where
     pub fn new(display: &'a D) -> Self {
         Renderer {
             display: display,
-            node_program: glium::Program::from_source(
+            node_program: program::build(
                 display,
+                "node",
                 include_str!("node.vert"),
                 include_str!("node.frag"),
                 Some(include_str!("node.geom")),
-            ).unwrap(),
-            round_edge_program: glium::Program::from_source(
+            ),
+            round_edge_program: program::build(
                 display,
+                "round edge",
                 include_str!("node.vert"),
                 include_str!("node.frag"),
                 Some(include_str!("round_edge.geom")),
-            ).unwrap(),
-            flat_edge_program: glium::Program::from_source(
+            ),
+            flat_edge_program: program::build(
                 display,
+                "flat edge",
                 include_str!("node.vert"),
                 include_str!("node.frag"),
                 Some(include_str!("flat_edge.geom")),
-            ).unwrap(),
+            ),
+            sdf_node_program: program::build(
+                display,
+                "sdf node",
+                include_str!("node.vert"),
+                include_str!("node_sdf.frag"),
+                Some(include_str!("node.geom")),
+            ),
             params: glium::DrawParameters {
                 blend: glium::Blend::alpha_blending(),
                 ..Default::default()
             },
+            scratch: RefCell::new(None),
+            debug: DebugView::Off,
+            validate: false,
+            warned_upload: Cell::new(false),
         }
     }
 
+    /// Change what is drawn to diagnose the triangles emitted for every node and edge.
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        self.debug = view;
+    }
+
+    /// Check everything passed to the renderer for NaN or infinite values, negative radii, zero length edges, and
+    /// colors outside of `0.0..=1.0`. Each problem is logged as a warning with the index and field of the element,
+    /// and a draw with any problems is skipped.
+    ///
+    /// This is off by default since it costs a pass over every element each time something is drawn.
+    pub fn set_validation(&mut self, enabled: bool) {
+        self.validate = enabled;
+    }
+
+    /// Test everything drawn against the depth buffer of the target so it is hidden behind existing geometry.
+    ///
+    /// If `write` is true, the depth of glow that isn't fully transparent is written as well, which lets it hide
+    /// geometry drawn afterwards. The target must have a depth buffer, such as the one the application already
+    /// rendered its 3D world into. By default there is no depth testing or writing.
+    pub fn set_depth_test(&mut self, test: bool, write: bool) {
+        self.params.depth = glium::Depth {
+            test: if test {
+                glium::DepthTest::IfLessOrEqual
+            } else {
+                glium::DepthTest::Overwrite
+            },
+            write: write,
+            ..Default::default()
+        };
+    }
+
+    /// Draw a stereo image by calling `draw` once for each eye with the renderer and matrices to draw that eye with.
+    ///
+    /// The matrices of the eyes are made from a single camera given by `modelview` and `projection`. Everything that
+    /// `draw` draws with the renderer is limited to the color channels or half of the target of the current eye.
+    /// The depth buffer of the target belongs to the caller and is never cleared here. In side-by-side mode each eye
+    /// only touches its own half of it. In anaglyph mode both eyes share it, so with depth writes enabled the first
+    /// eye's glow can hide the second's; draw the anaglyph without depth writes to avoid that.
+    pub fn render_stereo<S, F>(
+        &mut self,
+        target: &mut S,
+        stereo: &Stereo,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        mut draw: F,
+    ) where
+        S: Surface,
+        F: FnMut(&Self, &mut S, [[f32; 4]; 4], [[f32; 4]; 4]),
+    {
+        let (width, height) = target.get_dimensions();
+        let saved = self.params.clone();
+        for (eye, &side) in [-1.0f32, 1.0].iter().enumerate() {
+            match stereo.mode {
+                StereoMode::Anaglyph => {
+                    self.params.color_mask = if eye == 0 {
+                        (true, false, false, true)
+                    } else {
+                        (false, true, true, true)
+                    };
+                }
+                StereoMode::SideBySide => {
+                    self.params.viewport = Some(glium::Rect {
+                        left: eye as u32 * width / 2,
+                        bottom: 0,
+                        width: width / 2,
+                        height: height,
+                    });
+                }
+            }
+            let (modelview, projection) = stereo.eye(side, modelview, projection);
+            draw(self, target, modelview, projection);
+        }
+        self.params = saved;
+    }
+
     /// Take a modelview matrix, projection matrix, and a series of nodes and draw them in parallel on the GPU.
     pub fn render_nodes<S>(
         &self,
@@ -92,22 +288,80 @@ where
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, nodes).unwrap();
+        self.render_nodes_with_uniforms(target, modelview, projection, nodes, &self.node_program, &[]);
+    }
+
+    /// Draw nodes like `render_nodes` with a custom `program`, adding the `extra` uniforms to the ones the renderer
+    /// sets.
+    ///
+    /// This lets custom shader code receive data that changes every frame, like
+    /// `&[("u_time", UniformValue::Float(t))]`. The program is fed the fields of `Node` as vertex attributes of the
+    /// same names, just like the stock node shaders, and uniforms it doesn't declare are ignored.
+    pub fn render_nodes_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        nodes: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(nodes)) {
+            return;
+        }
+        let vertex_buffer = self.upload(nodes);
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.node_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            &vertex_buffer,
+            vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
+    }
+
+    /// Take a modelview matrix, projection matrix, and a series of nodes and draw them in parallel on the GPU with boundaries computed from exact distances.
+    ///
+    /// The edges of the inner disc and the glow are softened over exactly one pixel using screen-space derivatives,
+    /// so nodes stay crisp no matter how far in they are zoomed.
+    pub fn render_nodes_sdf<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        nodes: &[Node],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate(nodes)) {
+            return;
+        }
+        let vertex_buffer = self.upload(nodes);
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+        self.draw(
+            target,
+            &vertex_buffer,
+            vertex_buffer.len(),
+            indices,
+            &self.sdf_node_program,
+            &uniforms,
+        );
     }
 
     /// Take a modelview matrix, projection matrix, and a series of lines (edges) and draw them in parallel on the GPU.
@@ -122,22 +376,43 @@ where
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, edges).unwrap();
+        self.render_edges_round_with_uniforms(target, modelview, projection, edges, &self.round_edge_program, &[]);
+    }
+
+    /// Draw round edges like `render_edges_round` with a custom `program` and `extra` uniforms, as
+    /// `render_nodes_with_uniforms` does.
+    pub fn render_edges_round_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        edges: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate_edges3(edges)) {
+            return;
+        }
+        let vertex_buffer = self.upload(edges);
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.round_edge_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            &vertex_buffer,
+            vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
     }
 
     /// Take a modelview matrix, projection matrix, and a series of lines (edges) and draw them in parallel on the GPU.
@@ -152,21 +427,275 @@ where
     ) where
         S: Surface,
     {
-        let vertex_buffer = glium::VertexBuffer::new(self.display, edges).unwrap();
+        self.render_edges_flat_with_uniforms(target, modelview, projection, edges, &self.flat_edge_program, &[]);
+    }
+
+    /// Draw flat edges like `render_edges_flat` with a custom `program` and `extra` uniforms, as
+    /// `render_nodes_with_uniforms` does.
+    pub fn render_edges_flat_with_uniforms<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        edges: &[Node],
+        program: &glium::Program,
+        extra: &[(&str, UniformValue)],
+    ) where
+        S: Surface,
+    {
+        if !self.check(|| validate::validate_edges3(edges)) {
+            return;
+        }
+        let vertex_buffer = self.upload(edges);
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
 
         let uniforms = uniform! {
             modelview: modelview,
             projection: projection,
         };
-        target
-            .draw(
-                &vertex_buffer,
-                &indices,
-                &self.flat_edge_program,
-                &uniforms,
-                &self.params,
-            )
-            .unwrap();
+        self.draw(
+            target,
+            &vertex_buffer,
+            vertex_buffer.len(),
+            indices,
+            program,
+            &ExtraUniforms {
+                uniforms: &uniforms,
+                extra: extra,
+            },
+        );
+    }
+
+    /// Take an iterator of nodes and draw them in parallel on the GPU.
+    ///
+    /// The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_nodes_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        nodes: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            nodes,
+            glium::index::PrimitiveType::Points,
+            &self.node_program,
+        );
+    }
+
+    /// Take an iterator of lines (edges) and draw them in parallel on the GPU.
+    ///
+    /// These have round ends. The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_edges_round_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        edges: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.round_edge_program,
+        );
+    }
+
+    /// Take an iterator of lines (edges) and draw them in parallel on the GPU.
+    ///
+    /// These have flat ends. The nodes are written straight into a vertex buffer that is reused between calls.
+    pub fn render_edges_flat_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        edges: I,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        self.draw_iter(
+            target,
+            modelview,
+            projection,
+            edges,
+            glium::index::PrimitiveType::LinesList,
+            &self.flat_edge_program,
+        );
+    }
+
+    /// Resolve a series of styled nodes against a StyleSheet and draw them in parallel on the GPU.
+    pub fn render_styled_nodes<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        sheet: &StyleSheet,
+        nodes: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let nodes = nodes.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_nodes(target, modelview, projection, &nodes);
+    }
+
+    /// Resolve a series of styled lines (edges) against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These have round ends.
+    pub fn render_styled_edges_round<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        sheet: &StyleSheet,
+        edges: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let edges = edges.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_edges_round(target, modelview, projection, &edges);
+    }
+
+    /// Resolve a series of styled lines (edges) against a StyleSheet and draw them in parallel on the GPU.
+    ///
+    /// These have flat ends.
+    pub fn render_styled_edges_flat<S>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        sheet: &StyleSheet,
+        edges: &[StyledNode],
+    ) where
+        S: Surface,
+    {
+        let edges = edges.iter().map(|n| n.resolve(sheet)).collect::<Vec<_>>();
+        self.render_edges_flat(target, modelview, projection, &edges);
+    }
+
+    /// Copy elements into a new vertex buffer, which is slow when done every frame.
+    fn upload<T>(&self, elements: &[T]) -> glium::VertexBuffer<T>
+    where
+        T: Copy + glium::Vertex,
+    {
+        if self.warned_upload.replace(true) {
+            trace!("allocating a vertex buffer for {} elements", elements.len());
+        } else {
+            warn!("allocating a new vertex buffer on every draw, use the _iter methods to reuse one instead");
+        }
+        program::allocate(glium::VertexBuffer::new(self.display, elements), "a vertex buffer")
+    }
+
+    /// Tell whether the elements are fine to draw, which they are unless validation is enabled and finds problems.
+    fn check<F>(&self, errors: F) -> bool
+    where
+        F: FnOnce() -> Vec<ValidationError>,
+    {
+        !self.validate || validate::report(&errors())
+    }
+
+    fn draw<'b, S, T, I, U>(
+        &self,
+        target: &mut S,
+        vertices: &'b glium::VertexBuffer<T>,
+        len: usize,
+        indices: I,
+        program: &glium::Program,
+        uniforms: &U,
+    ) where
+        S: Surface,
+        T: Copy,
+        I: Into<glium::index::IndicesSource<'b>> + Copy,
+        U: glium::uniforms::Uniforms,
+    {
+        for (view, params) in self.debug.passes(&self.params) {
+            let result = target.draw(
+                vertices.slice(0..len).unwrap(),
+                indices,
+                program,
+                &DebugUniforms { uniforms: uniforms, view: view },
+                &params,
+            );
+            if let Err(e) = result {
+                error!("draw call failed: {}", e);
+            }
+        }
+    }
+
+    fn draw_iter<S, I>(
+        &self,
+        target: &mut S,
+        modelview: [[f32; 4]; 4],
+        projection: [[f32; 4]; 4],
+        nodes: I,
+        primitive: glium::index::PrimitiveType,
+        program: &glium::Program,
+    ) where
+        S: Surface,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: GlowNode,
+    {
+        let nodes = nodes.into_iter();
+        let len = nodes.len();
+        if len == 0 {
+            return;
+        }
+
+        let mut scratch = self.scratch.borrow_mut();
+        if scratch.as_ref().map(|b| b.len() < len).unwrap_or(true) {
+            debug!("growing the reusable vertex buffer to {} elements", len.next_power_of_two());
+            *scratch = Some(program::allocate(
+                glium::VertexBuffer::empty_dynamic(self.display, len.next_power_of_two()),
+                "the reusable vertex buffer",
+            ));
+        }
+        let vertex_buffer = scratch.as_mut().unwrap();
+        {
+            let mut mapping = vertex_buffer.map_write();
+            let mut errors = Vec::new();
+            // Edges are validated in pairs to find those with zero length.
+            let mut ends = Vec::new();
+            let pairs = self.validate && primitive == glium::index::PrimitiveType::LinesList;
+            for (i, node) in nodes.enumerate().take(len) {
+                let node = node.glow_node();
+                if self.validate {
+                    node.validate(i, &mut errors);
+                }
+                if pairs {
+                    ends.push(node.position);
+                }
+                mapping.set(i, node);
+            }
+            validate::zero_length(ends.chunks(2).map(|e| e.iter()), 2, &mut errors);
+            if !validate::report(&errors) {
+                return;
+            }
+        }
+        let indices = glium::index::NoIndices(primitive);
+
+        let uniforms = uniform! {
+            modelview: modelview,
+            projection: projection,
+        };
+
+        self.draw(target, vertex_buffer, len, indices, program, &uniforms);
     }
 }
@@ -6,9 +6,18 @@ in vec4 ffalloff_color;
 in float finner_radius;
 in float ffalloff_radius;
 in float ffalloff;
+uniform int debug_view;
 out vec4 color;
 
 void main() {
+    // The debug views show the emitted triangles instead of the glow.
+    if (debug_view == 1) {
+        color = vec4(0.0, 1.0, 0.0, 1.0);
+        return;
+    } else if (debug_view == 2) {
+        color = vec4(0.0625, 0.015625, 0.00390625, 1.0);
+        return;
+    }
     float length = length(delta);
     if (length <= finner_radius) {
         float travel = length / finner_radius;
@@ -18,4 +27,8 @@ void main() {
         color = vec4(ffalloff_color.xyz,
             ffalloff_color.a * max(0.0, 1.0 - pow((length - finner_radius) / ffalloff_radius, ffalloff)));
     }
+    // Fully transparent fragments must not write depth or they would hide geometry behind the glow.
+    if (color.a <= 0.0) {
+        discard;
+    }
 }
\ No newline at end of file
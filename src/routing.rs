@@ -0,0 +1,306 @@
+use render2::Node;
+
+/// An axis aligned rectangle, used as an obstacle for edge routing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Rect {
+    /// The bounding box of the inner disc of a node.
+    pub fn around(node: &Node) -> Rect {
+        let [x, y] = node.position;
+        let r = node.inner_radius;
+        Rect {
+            min: [x - r, y - r],
+            max: [x + r, y + r],
+        }
+    }
+
+    /// Grow the rectangle by `margin` on every side.
+    pub fn expand(&self, margin: f32) -> Rect {
+        Rect {
+            min: [self.min[0] - margin, self.min[1] - margin],
+            max: [self.max[0] + margin, self.max[1] + margin],
+        }
+    }
+
+    /// Whether a point is inside the rectangle or on its boundary.
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.min[0] && point[0] <= self.max[0] && point[1] >= self.min[1] && point[1] <= self.max[1]
+    }
+
+    /// The smallest rectangle that contains both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: [self.min[0].min(other.min[0]), self.min[1].min(other.min[1])],
+            max: [self.max[0].max(other.max[0]), self.max[1].max(other.max[1])],
+        }
+    }
+
+    /// Whether the rectangles overlap or touch.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min[0] <= other.max[0] && self.max[0] >= other.min[0] && self.min[1] <= other.max[1]
+            && self.max[1] >= other.min[1]
+    }
+
+    /// Whether an axis aligned segment passes through the interior of the rectangle.
+    fn blocks(&self, a: [f32; 2], b: [f32; 2]) -> bool {
+        a[0].min(b[0]) < self.max[0] && a[0].max(b[0]) > self.min[0] && a[1].min(b[1]) < self.max[1]
+            && a[1].max(b[1]) > self.min[1]
+    }
+}
+
+/// Find a route made only of horizontal and vertical segments from `from` to `to` that avoids `obstacles`.
+///
+/// Obstacles are grown by `margin` and obstacles that contain either end are ignored, so the boxes of the nodes being
+/// connected can be passed along with all the others. Routes with fewer bends are preferred, then shorter routes.
+/// If no candidate route is clear, the one that crosses the fewest obstacles is returned.
+/// The route includes both end points.
+pub fn route_orthogonal(from: [f32; 2], to: [f32; 2], obstacles: &[Rect], margin: f32) -> Vec<[f32; 2]> {
+    let obstacles = obstacles
+        .iter()
+        .map(|o| o.expand(margin))
+        .filter(|o| !o.contains(from) && !o.contains(to))
+        .collect::<Vec<_>>();
+
+    let mut candidates = vec![
+        vec![from, [to[0], from[1]], to],
+        vec![from, [from[0], to[1]], to],
+    ];
+    // Z shaped routes bend at the midpoint or just outside of any obstacle.
+    let mut xs = vec![0.5 * (from[0] + to[0])];
+    let mut ys = vec![0.5 * (from[1] + to[1])];
+    for o in &obstacles {
+        xs.push(o.min[0]);
+        xs.push(o.max[0]);
+        ys.push(o.min[1]);
+        ys.push(o.max[1]);
+    }
+    for &x in &xs {
+        candidates.push(vec![from, [x, from[1]], [x, to[1]], to]);
+    }
+    for &y in &ys {
+        candidates.push(vec![from, [from[0], y], [to[0], y], to]);
+    }
+
+    let crossings = |route: &Vec<[f32; 2]>| {
+        route
+            .windows(2)
+            .map(|s| obstacles.iter().filter(|o| o.blocks(s[0], s[1])).count())
+            .sum::<usize>()
+    };
+    let length = |route: &Vec<[f32; 2]>| {
+        route
+            .windows(2)
+            .map(|s| (s[1][0] - s[0][0]).abs() + (s[1][1] - s[0][1]).abs())
+            .sum::<f32>()
+    };
+
+    let mut best = candidates
+        .into_iter()
+        .map(|route| simplify(&route))
+        .map(|route| (crossings(&route), route.len(), length(&route), route))
+        .min_by(|a, b| {
+            (a.0, a.1)
+                .cmp(&(b.0, b.1))
+                .then(a.2.partial_cmp(&b.2).unwrap_or(::std::cmp::Ordering::Equal))
+        })
+        .unwrap()
+        .3;
+    best.dedup();
+    best
+}
+
+/// Remove points that don't change the direction of a route.
+fn simplify(route: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let mut simple: Vec<[f32; 2]> = Vec::with_capacity(route.len());
+    for &point in route {
+        if simple.last() == Some(&point) {
+            continue;
+        }
+        if simple.len() >= 2 {
+            let a = simple[simple.len() - 2];
+            let b = simple[simple.len() - 1];
+            let collinear = (a[0] == b[0] && b[0] == point[0]) || (a[1] == b[1] && b[1] == point[1]);
+            if collinear {
+                simple.pop();
+            }
+        }
+        simple.push(point);
+    }
+    simple
+}
+
+/// Replace every corner of a route with a curve of the given radius, sampled with `steps` segments.
+///
+/// The radius is reduced at corners where the neighboring segments are too short for it.
+pub fn round_corners(route: &[[f32; 2]], radius: f32, steps: usize) -> Vec<[f32; 2]> {
+    if route.len() < 3 || radius <= 0.0 || steps == 0 {
+        return route.to_vec();
+    }
+    let mut rounded = vec![route[0]];
+    for i in 1..route.len() - 1 {
+        let (a, corner, b) = (route[i - 1], route[i], route[i + 1]);
+        let len_in = ((corner[0] - a[0]).powi(2) + (corner[1] - a[1]).powi(2)).sqrt();
+        let len_out = ((b[0] - corner[0]).powi(2) + (b[1] - corner[1]).powi(2)).sqrt();
+        let r = radius.min(0.5 * len_in).min(0.5 * len_out);
+        if r <= 0.0 {
+            rounded.push(corner);
+            continue;
+        }
+        let start = [
+            corner[0] + (a[0] - corner[0]) * r / len_in,
+            corner[1] + (a[1] - corner[1]) * r / len_in,
+        ];
+        let end = [
+            corner[0] + (b[0] - corner[0]) * r / len_out,
+            corner[1] + (b[1] - corner[1]) * r / len_out,
+        ];
+        // A quadratic bezier with its control point at the corner is very close to a quarter circle.
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let u = 1.0 - t;
+            rounded.push([
+                u * u * start[0] + 2.0 * u * t * corner[0] + t * t * end[0],
+                u * u * start[1] + 2.0 * u * t * corner[1] + t * t * end[1],
+            ]);
+        }
+    }
+    rounded.push(route[route.len() - 1]);
+    rounded
+}
+
+/// Route an orthogonal edge between two nodes and turn it into a polyline for `Renderer::render_polylines`.
+///
+/// The style of the edge is interpolated from `from` to `to` along the length of the route.
+pub fn orthogonal_edge(
+    from: &Node,
+    to: &Node,
+    obstacles: &[Rect],
+    margin: f32,
+    corner_radius: f32,
+) -> Vec<Node> {
+    let route = round_corners(
+        &route_orthogonal(from.position, to.position, obstacles, margin),
+        corner_radius,
+        8,
+    );
+    let mut lengths = vec![0.0];
+    for s in route.windows(2) {
+        let last = *lengths.last().unwrap();
+        lengths.push(last + ((s[1][0] - s[0][0]).powi(2) + (s[1][1] - s[0][1]).powi(2)).sqrt());
+    }
+    let total = lengths.last().cloned().unwrap_or(0.0).max(::std::f32::EPSILON);
+    route
+        .iter()
+        .zip(lengths)
+        .map(|(&position, length)| {
+            let mut node = from.lerp(to, length / total);
+            node.position = position;
+            node
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use style::Style;
+
+    fn axis_aligned(route: &[[f32; 2]]) -> bool {
+        route.windows(2).all(|s| s[0][0] == s[1][0] || s[0][1] == s[1][1])
+    }
+
+    #[test]
+    fn rect_operations() {
+        let a = Rect {
+            min: [0.0, 0.0],
+            max: [1.0, 1.0],
+        };
+        let b = Rect {
+            min: [2.0, -1.0],
+            max: [3.0, 0.5],
+        };
+        assert!(a.contains([1.0, 0.5]));
+        assert!(!a.contains([1.1, 0.5]));
+        assert!(!a.intersects(&b));
+        assert!(a.expand(1.0).intersects(&b));
+        assert_eq!(
+            a.union(&b),
+            Rect {
+                min: [0.0, -1.0],
+                max: [3.0, 1.0],
+            }
+        );
+    }
+
+    #[test]
+    fn routes_are_direct_without_obstacles() {
+        assert_eq!(route_orthogonal([0.0, 0.0], [2.0, 0.0], &[], 0.1), vec![[0.0, 0.0], [2.0, 0.0]]);
+        let route = route_orthogonal([0.0, 0.0], [2.0, 2.0], &[], 0.1);
+        assert_eq!(route.len(), 3);
+        assert!(axis_aligned(&route));
+    }
+
+    #[test]
+    fn routes_go_around_obstacles() {
+        let obstacle = Rect {
+            min: [1.5, -0.5],
+            max: [2.5, 0.5],
+        };
+        let route = route_orthogonal([0.0, 0.0], [2.0, 2.0], &[obstacle], 0.1);
+        assert_eq!(route, vec![[0.0, 0.0], [0.0, 2.0], [2.0, 2.0]]);
+
+        // A wall between the ends forces a Z shaped route around it.
+        let wall = Rect {
+            min: [0.9, -1.0],
+            max: [1.1, 1.0],
+        };
+        let route = route_orthogonal([0.0, 0.0], [2.0, 0.0], &[wall], 0.1);
+        assert!(axis_aligned(&route));
+        assert!(route.windows(2).all(|s| !wall.expand(0.1).blocks(s[0], s[1])));
+    }
+
+    #[test]
+    fn obstacles_around_the_ends_are_ignored() {
+        let node = Rect {
+            min: [-0.5, -0.5],
+            max: [0.5, 0.5],
+        };
+        assert_eq!(route_orthogonal([0.0, 0.0], [2.0, 0.0], &[node], 0.1), vec![[0.0, 0.0], [2.0, 0.0]]);
+    }
+
+    #[test]
+    fn simplify_drops_repeated_and_collinear_points() {
+        let route = [[0.0, 0.0], [0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 1.0]];
+        assert_eq!(simplify(&route), vec![[0.0, 0.0], [2.0, 0.0], [2.0, 1.0]]);
+    }
+
+    #[test]
+    fn round_corners_keeps_the_ends_and_limits_the_radius() {
+        let route = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+        let rounded = round_corners(&route, 0.2, 4);
+        assert_eq!(rounded.len(), 7);
+        assert_eq!(rounded[0], [0.0, 0.0]);
+        assert_eq!(rounded[1], [0.8, 0.0]);
+        assert_eq!(rounded[5], [1.0, 0.2]);
+        assert_eq!(rounded[6], [1.0, 1.0]);
+        // The corner is only one unit from the ends, so the radius can't be more than half of that.
+        assert_eq!(round_corners(&route, 5.0, 4)[1], [0.5, 0.0]);
+        assert_eq!(round_corners(&route, 0.0, 4), route.to_vec());
+    }
+
+    #[test]
+    fn orthogonal_edges_blend_the_styles_of_their_ends() {
+        let from = Style::default().node2([0.0, 0.0]);
+        let mut to = Style::default().node2([2.0, 2.0]);
+        to.inner_color = [0.0, 0.0, 0.0, 1.0];
+        let edge = orthogonal_edge(&from, &to, &[], 0.1, 0.2);
+        assert_eq!(edge[0].position, from.position);
+        assert_eq!(edge[0].inner_color, from.inner_color);
+        assert_eq!(edge.last().unwrap().position, to.position);
+        assert_eq!(edge.last().unwrap().inner_color, to.inner_color);
+    }
+}
@@ -0,0 +1,104 @@
+/// How a Scale maps its domain onto its range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScaleKind {
+    Linear,
+    /// Maps `ln(1 + value)` linearly, which keeps zero (such as the degree of an isolated node) usable.
+    Log,
+    /// Maps the square root of the value linearly, which makes the area of a node proportional to the value.
+    Sqrt,
+}
+
+/// A Scale maps a metric of every node, such as its degree, onto a visual property such as its radius.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Scale {
+    pub kind: ScaleKind,
+    /// The metric values that map onto the ends of the range.
+    pub domain: [f32; 2],
+    pub range: [f32; 2],
+    /// Whether values outside of the domain are limited to the range instead of extrapolated.
+    pub clamp: bool,
+}
+
+impl Scale {
+    /// Make a clamped scale.
+    pub fn new(kind: ScaleKind, domain: [f32; 2], range: [f32; 2]) -> Self {
+        Scale {
+            kind: kind,
+            domain: domain,
+            range: range,
+            clamp: true,
+        }
+    }
+
+    /// Make a clamped scale whose domain goes from the smallest to the largest of `values`.
+    pub fn fit(kind: ScaleKind, values: &[f32], range: [f32; 2]) -> Self {
+        let domain = values
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold(None::<[f32; 2]>, |domain, &v| match domain {
+                Some([min, max]) => Some([v.min(min), v.max(max)]),
+                None => Some([v, v]),
+            })
+            .unwrap_or([0.0, 1.0]);
+        Scale::new(kind, domain, range)
+    }
+
+    /// Map a value from the domain into the range.
+    ///
+    /// If the domain is empty every value maps to the middle of the range.
+    pub fn map(&self, value: f32) -> f32 {
+        let transform = |v: f32| match self.kind {
+            ScaleKind::Linear => v,
+            ScaleKind::Log => v.ln_1p(),
+            ScaleKind::Sqrt => v.sqrt(),
+        };
+        let (low, high) = (transform(self.domain[0]), transform(self.domain[1]));
+        let mut t = if high == low {
+            0.5
+        } else {
+            (transform(value) - low) / (high - low)
+        };
+        if self.clamp {
+            t = t.max(0.0).min(1.0);
+        }
+        self.range[0] + (self.range[1] - self.range[0]) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_maps_and_clamps() {
+        let mut scale = Scale::new(ScaleKind::Linear, [0.0, 10.0], [1.0, 3.0]);
+        assert_eq!(scale.map(5.0), 2.0);
+        assert_eq!(scale.map(20.0), 3.0);
+        assert_eq!(scale.map(-5.0), 1.0);
+        scale.clamp = false;
+        assert_eq!(scale.map(20.0), 5.0);
+    }
+
+    #[test]
+    fn log_and_sqrt_compress_large_values() {
+        let log = Scale::new(ScaleKind::Log, [0.0, 99.0], [0.0, 1.0]);
+        assert_eq!(log.map(0.0), 0.0);
+        assert!((log.map(9.0) - 0.5).abs() < 1e-6);
+        let sqrt = Scale::new(ScaleKind::Sqrt, [0.0, 100.0], [0.0, 1.0]);
+        assert_eq!(sqrt.map(25.0), 0.5);
+    }
+
+    #[test]
+    fn fit_spans_the_finite_values() {
+        let scale = Scale::fit(ScaleKind::Linear, &[3.0, ::std::f32::NAN, 1.0, ::std::f32::INFINITY, 2.0], [0.0, 1.0]);
+        assert_eq!(scale.domain, [1.0, 3.0]);
+        assert_eq!(Scale::fit(ScaleKind::Linear, &[], [0.0, 1.0]).domain, [0.0, 1.0]);
+    }
+
+    #[test]
+    fn empty_domains_map_to_the_middle() {
+        let scale = Scale::fit(ScaleKind::Linear, &[4.0, 4.0], [2.0, 4.0]);
+        assert_eq!(scale.map(4.0), 3.0);
+        assert_eq!(scale.map(100.0), 3.0);
+    }
+}
@@ -0,0 +1,1305 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::slice;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use glium::{self, Surface};
+use glium::texture::Texture2d;
+use glium::uniforms::MagnifySamplerFilter;
+
+use pick;
+use post::GroupCompositor;
+use render2::{Lens, Node, Renderer};
+use style::{Style, StyleSheet};
+use theme::Theme;
+
+/// A node in a Scene.
+///
+/// If `style` is `None` or names a style that is not in the Scene's StyleSheet, the Theme's node style is used.
+#[derive(Clone, Debug, Default)]
+pub struct SceneNode {
+    pub position: [f32; 2],
+    pub style: Option<String>,
+    pub selected: bool,
+    /// The occlusion group of the node, see `Scene::render_grouped`.
+    pub group: usize,
+}
+
+/// An edge between two nodes in a Scene.
+///
+/// If `style` is `None` or names a style that is not in the Scene's StyleSheet, the Theme's edge style is used.
+#[derive(Clone, Debug, Default)]
+pub struct SceneEdge {
+    pub from: usize,
+    pub to: usize,
+    pub style: Option<String>,
+    pub selected: bool,
+    /// The occlusion group of the edge, see `Scene::render_grouped`.
+    pub group: usize,
+}
+
+/// A Scene owns a 2d graph along with the styles and Theme used to draw it.
+///
+/// Nodes and edges are referred to by the index returned when they are added, which stays valid until they are removed.
+/// Styles are resolved into vertices lazily, so any number of changes can be made between frames.
+pub struct Scene {
+    theme: Theme,
+    styles: StyleSheet,
+    nodes: Vec<Option<SceneNode>>,
+    edges: Vec<Option<SceneEdge>>,
+    node_vertices: Vec<Node>,
+    edge_vertices: Vec<Node>,
+    vertex_nodes: Vec<usize>,
+    vertex_edges: Vec<usize>,
+    dirty: bool,
+    damage: Damage,
+    updates: Option<(Sender<SceneUpdate>, Receiver<SceneUpdate>)>,
+    history: History,
+    /// The names of missing styles that have already been warned about, so each is only reported once.
+    warned_styles: HashSet<String>,
+}
+
+/// An element of a Scene, as passed to the `opacity` function of `Scene::render_with_opacity`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Element {
+    Node(usize),
+    Edge { edge: usize, from: usize, to: usize },
+}
+
+/// The previous contents of a node or edge slot, which restores it when undoing.
+#[derive(Clone, Debug)]
+enum Change {
+    Node(usize, Option<SceneNode>),
+    Edge(usize, Option<SceneEdge>),
+}
+
+/// The undo and redo stacks of a Scene, where every step is a group of changes.
+#[derive(Clone, Debug, Default)]
+struct History {
+    limit: usize,
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    transaction: Vec<Change>,
+    depth: usize,
+    /// The node that the latest change moved, whose further moves are merged into that change.
+    moved: Option<usize>,
+    /// The nodes that the latest step, a transaction, did nothing but move. Later transactions that only move some of
+    /// them are merged into it.
+    batch: Option<BTreeSet<usize>>,
+    /// The nodes moved by the open transaction.
+    moving: BTreeSet<usize>,
+    /// Whether the open transaction did anything other than move nodes.
+    changed: bool,
+}
+
+/// The elements of a Scene that changed since the damage was last taken with `Scene::take_damage`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Damage {
+    /// Everything must be redrawn, such as after a theme change or a removal.
+    pub all: bool,
+    pub nodes: HashSet<usize>,
+    pub edges: HashSet<usize>,
+}
+
+/// A change to a Scene queued from another thread with a SceneHandle.
+#[derive(Clone, Debug)]
+pub enum SceneUpdate {
+    /// Add a node, which gets the next index after every node added before it.
+    AddNode(SceneNode),
+    /// Add an edge, which is skipped if either of its nodes doesn't exist when it is applied.
+    AddEdge(SceneEdge),
+    RemoveNode(usize),
+    RemoveEdge(usize),
+    /// Move a batch of nodes, such as the result of a layout step.
+    SetPositions(Vec<(usize, [f32; 2])>),
+    SetNodeStyle(usize, Option<String>),
+    SetEdgeStyle(usize, Option<String>),
+    Clear,
+}
+
+/// A SceneHandle lets other threads, such as a layout or IO thread, queue changes to a Scene.
+///
+/// The changes are applied in the order they were sent when the render thread calls `Scene::drain_updates`,
+/// which is usually done once per frame. Handles are made with `Scene::handle` and can be cloned and sent
+/// to any number of threads.
+#[derive(Clone, Debug)]
+pub struct SceneHandle {
+    sender: Sender<SceneUpdate>,
+}
+
+impl SceneHandle {
+    /// Queue a change, returning false if the Scene no longer exists.
+    pub fn send(&self, update: SceneUpdate) -> bool {
+        self.sender.send(update).is_ok()
+    }
+
+    /// Queue adding a node.
+    pub fn add_node(&self, node: SceneNode) -> bool {
+        self.send(SceneUpdate::AddNode(node))
+    }
+
+    /// Queue adding an edge.
+    pub fn add_edge(&self, edge: SceneEdge) -> bool {
+        self.send(SceneUpdate::AddEdge(edge))
+    }
+
+    /// Queue moving a batch of nodes.
+    pub fn set_positions(&self, positions: Vec<(usize, [f32; 2])>) -> bool {
+        self.send(SceneUpdate::SetPositions(positions))
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new(Theme::default())
+    }
+}
+
+impl Scene {
+    /// Make an empty Scene that uses `theme`.
+    pub fn new(theme: Theme) -> Self {
+        Scene {
+            theme: theme,
+            styles: StyleSheet::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            node_vertices: Vec::new(),
+            edge_vertices: Vec::new(),
+            vertex_nodes: Vec::new(),
+            vertex_edges: Vec::new(),
+            dirty: true,
+            damage: Damage {
+                all: true,
+                ..Default::default()
+            },
+            updates: None,
+            history: History::default(),
+            warned_styles: HashSet::new(),
+        }
+    }
+
+    /// Get the Theme of the Scene.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Replace the Theme of the Scene and re-resolve the styles of every element.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.dirty = true;
+        self.damage.all = true;
+        self.update();
+    }
+
+    /// Get the StyleSheet that named element styles are resolved against.
+    pub fn styles(&self) -> &StyleSheet {
+        &self.styles
+    }
+
+    /// Modify the StyleSheet that named element styles are resolved against.
+    pub fn styles_mut(&mut self) -> &mut StyleSheet {
+        self.dirty = true;
+        self.damage.all = true;
+        &mut self.styles
+    }
+
+    /// Add a node and get its index.
+    pub fn add_node(&mut self, node: SceneNode) -> usize {
+        self.dirty = true;
+        self.nodes.push(Some(node));
+        let index = self.nodes.len() - 1;
+        self.record(Change::Node(index, None), None);
+        self.damage.nodes.insert(index);
+        index
+    }
+
+    /// Remove a node along with every edge connected to it.
+    pub fn remove_node(&mut self, node: usize) -> Option<SceneNode> {
+        self.node(node)?;
+        self.begin_transaction();
+        self.record_node(node);
+        let removed = self.nodes[node].take();
+        self.dirty = true;
+        self.damage.all = true;
+        for i in 0..self.edges.len() {
+            let connected = self.edges[i]
+                .as_ref()
+                .map(|e| e.from == node || e.to == node)
+                .unwrap_or(false);
+            if connected {
+                self.record_edge(i);
+                self.edges[i] = None;
+            }
+        }
+        self.commit_transaction();
+        removed
+    }
+
+    /// Remove every node and edge.
+    pub fn clear(&mut self) {
+        self.begin_transaction();
+        for i in 0..self.nodes.len() {
+            if self.nodes[i].is_some() {
+                self.record_node(i);
+            }
+        }
+        for i in 0..self.edges.len() {
+            if self.edges[i].is_some() {
+                self.record_edge(i);
+            }
+        }
+        self.commit_transaction();
+        self.nodes.clear();
+        self.edges.clear();
+        self.dirty = true;
+        self.damage.all = true;
+    }
+
+    /// Get a node by index.
+    pub fn node(&self, node: usize) -> Option<&SceneNode> {
+        self.nodes.get(node).and_then(|n| n.as_ref())
+    }
+
+    /// Iterate over the indices and values of every node.
+    pub fn nodes<'s>(&'s self) -> Box<dyn Iterator<Item = (usize, &'s SceneNode)> + 's> {
+        Box::new(
+            self.nodes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, n)| n.as_ref().map(|n| (i, n))),
+        )
+    }
+
+    /// Move a node.
+    ///
+    /// Consecutive moves of the same node, such as while it is dragged, are a single step of history.
+    pub fn set_node_position(&mut self, node: usize, position: [f32; 2]) {
+        if self.node(node).is_some() && self.history.moved != Some(node) {
+            self.record_move(node);
+        }
+        if let Some(n) = self.nodes.get_mut(node).and_then(|n| n.as_mut()) {
+            n.position = position;
+            self.dirty = true;
+            self.damage.nodes.insert(node);
+        }
+    }
+
+    /// Change the named style of a node.
+    pub fn set_node_style(&mut self, node: usize, style: Option<String>) {
+        if self.node(node).is_some() {
+            self.record_node(node);
+        }
+        if let Some(n) = self.nodes.get_mut(node).and_then(|n| n.as_mut()) {
+            n.style = style;
+            self.dirty = true;
+            self.damage.nodes.insert(node);
+        }
+    }
+
+    /// Select or deselect a node.
+    pub fn set_node_selected(&mut self, node: usize, selected: bool) {
+        if let Some(n) = self.nodes.get_mut(node).and_then(|n| n.as_mut()) {
+            n.selected = selected;
+            self.dirty = true;
+            self.damage.nodes.insert(node);
+        }
+    }
+
+    /// Add an edge and get its index.
+    ///
+    /// Panics if either end of the edge is not a node in the Scene.
+    pub fn add_edge(&mut self, edge: SceneEdge) -> usize {
+        assert!(
+            self.node(edge.from).is_some() && self.node(edge.to).is_some(),
+            "glowygraph::Scene::add_edge(): edge connects a node that is not in the scene"
+        );
+        self.dirty = true;
+        self.edges.push(Some(edge));
+        let index = self.edges.len() - 1;
+        self.record(Change::Edge(index, None), None);
+        self.damage.edges.insert(index);
+        index
+    }
+
+    /// Remove an edge.
+    pub fn remove_edge(&mut self, edge: usize) -> Option<SceneEdge> {
+        if self.edge(edge).is_some() {
+            self.record_edge(edge);
+        }
+        let removed = self.edges.get_mut(edge).and_then(|e| e.take());
+        if removed.is_some() {
+            self.dirty = true;
+            self.damage.all = true;
+        }
+        removed
+    }
+
+    /// Get an edge by index.
+    pub fn edge(&self, edge: usize) -> Option<&SceneEdge> {
+        self.edges.get(edge).and_then(|e| e.as_ref())
+    }
+
+    /// Iterate over the indices and values of every edge.
+    pub fn edges<'s>(&'s self) -> Box<dyn Iterator<Item = (usize, &'s SceneEdge)> + 's> {
+        Box::new(
+            self.edges
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| e.as_ref().map(|e| (i, e))),
+        )
+    }
+
+    /// Change the named style of an edge.
+    pub fn set_edge_style(&mut self, edge: usize, style: Option<String>) {
+        if self.edge(edge).is_some() {
+            self.record_edge(edge);
+        }
+        if let Some(e) = self.edges.get_mut(edge).and_then(|e| e.as_mut()) {
+            e.style = style;
+            self.dirty = true;
+            self.damage.edges.insert(edge);
+        }
+    }
+
+    /// Select or deselect an edge.
+    pub fn set_edge_selected(&mut self, edge: usize, selected: bool) {
+        if let Some(e) = self.edges.get_mut(edge).and_then(|e| e.as_mut()) {
+            e.selected = selected;
+            self.dirty = true;
+            self.damage.edges.insert(edge);
+        }
+    }
+
+    /// Keep up to `limit` steps of history for `undo` and `redo`, where zero (the default) disables history.
+    ///
+    /// Adding, removing, moving and restyling nodes and edges is tracked, but selection, the theme and the style sheet
+    /// are not. Consecutive moves of the same node are merged into one step until something else changes or a
+    /// transaction begins or is committed. Likewise, consecutive transactions that only move nodes are merged into
+    /// one step while they move nothing but the nodes of the first, such as the frames of an animated layout.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.limit = limit;
+        self.history.moved = None;
+        self.history.batch = None;
+        self.trim_history();
+    }
+
+    /// Group every change until the matching `commit_transaction` into a single step of history.
+    ///
+    /// Transactions can be nested, in which case the outermost one makes the step.
+    pub fn begin_transaction(&mut self) {
+        self.history.depth += 1;
+        self.history.moved = None;
+    }
+
+    /// Finish a transaction started with `begin_transaction`.
+    pub fn commit_transaction(&mut self) {
+        if self.history.depth == 0 {
+            return;
+        }
+        self.history.depth -= 1;
+        if self.history.depth == 0 {
+            self.history.moved = None;
+            let moving = ::std::mem::replace(&mut self.history.moving, BTreeSet::new());
+            let changed = ::std::mem::replace(&mut self.history.changed, false);
+            if self.history.transaction.is_empty() {
+                return;
+            }
+            if !changed && self.history.batch.as_ref().map_or(false, |batch| moving.is_subset(batch)) {
+                // The latest step already holds where these nodes were before they moved.
+                self.history.transaction.clear();
+                return;
+            }
+            self.push_step();
+            if !changed {
+                self.history.batch = Some(moving);
+            }
+        }
+    }
+
+    /// Whether there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Whether there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Revert the last step of history, returning false if there was nothing to undo.
+    ///
+    /// An open transaction is committed first.
+    pub fn undo(&mut self) -> bool {
+        while self.history.depth > 0 {
+            self.commit_transaction();
+        }
+        self.history.moved = None;
+        self.history.batch = None;
+        match self.history.undo.pop() {
+            Some(step) => {
+                let step = self.restore(step);
+                self.history.redo.push(step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply the last undone step again, returning false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.history.moved = None;
+        self.history.batch = None;
+        match self.history.redo.pop() {
+            Some(step) => {
+                let step = self.restore(step);
+                self.history.undo.push(step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get a handle that other threads can use to queue changes to the Scene.
+    pub fn handle(&mut self) -> SceneHandle {
+        let sender = self.updates.get_or_insert_with(mpsc::channel).0.clone();
+        SceneHandle { sender: sender }
+    }
+
+    /// Apply every change queued with a SceneHandle and get how many there were.
+    pub fn drain_updates(&mut self) -> usize {
+        let updates = match self.updates {
+            Some((_, ref receiver)) => receiver.try_iter().collect::<Vec<_>>(),
+            None => return 0,
+        };
+        let count = updates.len();
+        for update in updates {
+            match update {
+                SceneUpdate::AddNode(node) => {
+                    self.add_node(node);
+                }
+                SceneUpdate::AddEdge(edge) => {
+                    if self.node(edge.from).is_some() && self.node(edge.to).is_some() {
+                        self.add_edge(edge);
+                    } else {
+                        warn!("skipping queued edge from {} to {} since a node is missing", edge.from, edge.to);
+                    }
+                }
+                SceneUpdate::RemoveNode(node) => {
+                    self.remove_node(node);
+                }
+                SceneUpdate::RemoveEdge(edge) => {
+                    self.remove_edge(edge);
+                }
+                SceneUpdate::SetPositions(positions) => {
+                    self.begin_transaction();
+                    for (node, position) in positions {
+                        self.set_node_position(node, position);
+                    }
+                    self.commit_transaction();
+                }
+                SceneUpdate::SetNodeStyle(node, style) => self.set_node_style(node, style),
+                SceneUpdate::SetEdgeStyle(edge, style) => self.set_edge_style(edge, style),
+                SceneUpdate::Clear => self.clear(),
+            }
+        }
+        count
+    }
+
+    /// Add a change to the open transaction, or make it a step of its own, where `moved` is the node it moves if
+    /// that is all it does.
+    fn record(&mut self, change: Change, moved: Option<usize>) {
+        if self.history.limit == 0 {
+            return;
+        }
+        if self.history.depth > 0 {
+            match moved {
+                Some(node) => {
+                    self.history.moving.insert(node);
+                }
+                None => self.history.changed = true,
+            }
+        }
+        self.history.moved = moved;
+        self.history.transaction.push(change);
+        if self.history.depth == 0 {
+            // A change outside of a transaction is a step of its own.
+            self.push_step();
+        }
+    }
+
+    /// Turn the changes of the finished transaction into a step of history, if there were any.
+    fn push_step(&mut self) {
+        if !self.history.transaction.is_empty() {
+            let step = ::std::mem::replace(&mut self.history.transaction, Vec::new());
+            self.history.batch = None;
+            self.history.undo.push(step);
+            self.history.redo.clear();
+            self.trim_history();
+        }
+    }
+
+    fn record_node(&mut self, node: usize) {
+        let previous = self.nodes.get(node).cloned().unwrap_or(None);
+        self.record(Change::Node(node, previous), None);
+    }
+
+    fn record_move(&mut self, node: usize) {
+        let previous = self.nodes.get(node).cloned().unwrap_or(None);
+        self.record(Change::Node(node, previous), Some(node));
+    }
+
+    fn record_edge(&mut self, edge: usize) {
+        let previous = self.edges.get(edge).cloned().unwrap_or(None);
+        self.record(Change::Edge(edge, previous), None);
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.history.undo.len().saturating_sub(self.history.limit);
+        self.history.undo.drain(..excess);
+    }
+
+    /// Put back the previous contents of every slot in a step, latest first, and get the step that reverts it.
+    fn restore(&mut self, step: Vec<Change>) -> Vec<Change> {
+        self.dirty = true;
+        step.into_iter()
+            .rev()
+            .map(|change| match change {
+                Change::Node(i, value) => {
+                    if self.nodes.len() <= i {
+                        self.nodes.resize(i + 1, None);
+                    }
+                    if value.is_some() && self.nodes[i].is_some() {
+                        self.damage.nodes.insert(i);
+                    } else {
+                        self.damage.all = true;
+                    }
+                    Change::Node(i, ::std::mem::replace(&mut self.nodes[i], value))
+                }
+                Change::Edge(i, value) => {
+                    if self.edges.len() <= i {
+                        self.edges.resize(i + 1, None);
+                    }
+                    if value.is_some() && self.edges[i].is_some() {
+                        self.damage.edges.insert(i);
+                    } else {
+                        self.damage.all = true;
+                    }
+                    Change::Edge(i, ::std::mem::replace(&mut self.edges[i], value))
+                }
+            })
+            .collect()
+    }
+
+    /// Resolve the styles of every element into vertices if anything changed since the last update.
+    pub fn update(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.node_vertices.clear();
+        self.edge_vertices.clear();
+        self.vertex_nodes.clear();
+        self.vertex_edges.clear();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(ref node) = *node {
+                let style = self.resolve(&node.style, node.selected, &self.theme.node);
+                self.node_vertices.push(style.node2(node.position));
+                self.vertex_nodes.push(i);
+            }
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            if let Some(ref edge) = *edge {
+                let style = self.resolve(&edge.style, edge.selected, &self.theme.edge);
+                // Edges are only added between existing nodes and are removed with their nodes.
+                let from = self.nodes[edge.from].as_ref().unwrap().position;
+                let to = self.nodes[edge.to].as_ref().unwrap().position;
+                self.edge_vertices.push(style.node2(from));
+                self.edge_vertices.push(style.node2(to));
+                self.vertex_edges.push(i);
+            }
+        }
+        let missing = self.nodes
+            .iter()
+            .filter_map(|n| n.as_ref().and_then(|n| n.style.as_ref()))
+            .chain(self.edges.iter().filter_map(|e| e.as_ref().and_then(|e| e.style.as_ref())))
+            .filter(|name| self.styles.get(name).is_none() && !self.warned_styles.contains(*name))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for name in missing {
+            warn!("style {:?} is not in the style sheet, falling back to the theme", name);
+            self.warned_styles.insert(name);
+        }
+        self.dirty = false;
+    }
+
+    /// Get the resolved node vertices as of the last update.
+    pub fn node_vertices(&self) -> &[Node] {
+        &self.node_vertices
+    }
+
+    /// Get the resolved edge vertices (two per edge) as of the last update.
+    pub fn edge_vertices(&self) -> &[Node] {
+        &self.edge_vertices
+    }
+
+    /// Get the resolved vertex of a node as of the last update.
+    pub fn node_vertex(&self, node: usize) -> Option<&Node> {
+        self.vertex_nodes
+            .binary_search(&node)
+            .ok()
+            .map(|vertex| &self.node_vertices[vertex])
+    }
+
+    /// Get the two resolved vertices of an edge as of the last update.
+    pub fn edge_vertex_pair(&self, edge: usize) -> Option<&[Node]> {
+        self.vertex_edges
+            .binary_search(&edge)
+            .ok()
+            .map(|vertex| &self.edge_vertices[2 * vertex..2 * vertex + 2])
+    }
+
+    /// Take the set of elements that changed since the last call.
+    ///
+    /// Edges connected to changed nodes are included since they move with them.
+    pub fn take_damage(&mut self) -> Damage {
+        let mut damage = ::std::mem::replace(&mut self.damage, Damage::default());
+        if !damage.all && !damage.nodes.is_empty() {
+            for (i, edge) in self.edges() {
+                if damage.nodes.contains(&edge.from) || damage.nodes.contains(&edge.to) {
+                    damage.edges.insert(i);
+                }
+            }
+        }
+        damage
+    }
+
+    /// Find the node under `point` as of the last update, returning its index and the distance to its center.
+    ///
+    /// `modelview` is the matrix the scene is drawn with, which decides how large the nodes are around `point`.
+    pub fn pick(&self, modelview: [[f32; 3]; 3], point: [f32; 2]) -> Option<(usize, f32)> {
+        pick::pick_node(&self.node_vertices, modelview, point).map(|(vertex, d)| (self.vertex_nodes[vertex], d))
+    }
+
+    /// Find the `k` nodes closest to `point` as of the last update, nearest first, with the distance to each center.
+    pub fn k_nearest(&self, point: [f32; 2], k: usize) -> Vec<(usize, f32)> {
+        pick::k_nearest(&self.node_vertices, point, k)
+            .into_iter()
+            .map(|(vertex, d)| (self.vertex_nodes[vertex], d))
+            .collect()
+    }
+
+    /// Clear the target to the Theme background and draw the edges and then the nodes.
+    pub fn render<'a, D, S>(
+        &mut self,
+        renderer: &Renderer<'a, D>,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        self.render_with_opacity(renderer, target, modelview, projection, |_| 1.0);
+    }
+
+    /// Draw the scene like `render`, scaling the opacity of every element by what `opacity` returns for it.
+    ///
+    /// This is how effects like `EgoHighlight` dim parts of the scene without restyling it.
+    pub fn render_with_opacity<'a, D, S, F>(
+        &mut self,
+        renderer: &Renderer<'a, D>,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        mut opacity: F,
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+        F: FnMut(Element) -> f32,
+    {
+        self.update();
+        let [r, g, b, a] = self.theme.background;
+        target.clear_color(r, g, b, a);
+        if !self.edge_vertices.is_empty() {
+            let edges = &self.edges;
+            let vertex_edges = &self.vertex_edges;
+            let vertices = faded(&self.edge_vertices, 2, |vertex| {
+                let edge = vertex_edges[vertex];
+                let e = edges[edge].as_ref().unwrap();
+                opacity(Element::Edge {
+                    edge: edge,
+                    from: e.from,
+                    to: e.to,
+                })
+            });
+            renderer.render_edges_round(target, modelview, projection, &vertices);
+        }
+        if !self.node_vertices.is_empty() {
+            let vertex_nodes = &self.vertex_nodes;
+            let vertices = faded(&self.node_vertices, 1, |vertex| opacity(Element::Node(vertex_nodes[vertex])));
+            renderer.render_nodes(target, modelview, projection, &vertices);
+        }
+    }
+
+    /// Clear the target to the Theme background and draw the scene one occlusion group at a time.
+    ///
+    /// The glow of elements in the same group adds up, so dense clusters saturate, while overlapping groups are
+    /// combined by taking the brighter of them, so unrelated clusters that overlap don't blow out to white. Assign
+    /// groups with the `group` field of nodes and edges, such as by community. The combined glow is blended over the
+    /// background, so this works with light themes too. The renderer is switched to additive blending while drawing
+    /// and switched back afterwards.
+    pub fn render_grouped<'a, D, S>(
+        &mut self,
+        renderer: &mut Renderer<'a, D>,
+        compositor: &GroupCompositor,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        self.update();
+        let [r, g, b, a] = self.theme.background;
+        target.clear_color(r, g, b, a);
+
+        let mut groups: BTreeMap<usize, (Vec<Node>, Vec<Node>)> = BTreeMap::new();
+        for (vertex, &i) in self.vertex_nodes.iter().enumerate() {
+            let group = self.nodes[i].as_ref().unwrap().group;
+            groups.entry(group).or_default().0.push(self.node_vertices[vertex]);
+        }
+        for (vertex, &i) in self.vertex_edges.iter().enumerate() {
+            let group = self.edges[i].as_ref().unwrap().group;
+            let pair = &self.edge_vertices[2 * vertex..2 * vertex + 2];
+            groups.entry(group).or_default().1.extend_from_slice(pair);
+        }
+        let groups = groups.into_iter().map(|(_, vertices)| vertices).collect::<Vec<_>>();
+
+        renderer.set_additive(true);
+        compositor.render(target, groups.len(), |group, surface| {
+            let (ref nodes, ref edges) = groups[group];
+            if !edges.is_empty() {
+                renderer.render_edges_round(surface, modelview, projection, edges);
+            }
+            if !nodes.is_empty() {
+                renderer.render_nodes(surface, modelview, projection, nodes);
+            }
+        });
+        renderer.set_additive(false);
+    }
+
+    fn resolve(&self, name: &Option<String>, selected: bool, default: &Style) -> Style {
+        resolve(&self.theme, &self.styles, name, selected, default)
+    }
+}
+
+/// Get the style an element is drawn with, which is the selection style if it is selected.
+pub(crate) fn resolve(
+    theme: &Theme,
+    styles: &StyleSheet,
+    name: &Option<String>,
+    selected: bool,
+    default: &Style,
+) -> Style {
+    if selected {
+        return theme.selection;
+    }
+    name.as_ref()
+        .and_then(|name| styles.get(name))
+        .cloned()
+        .unwrap_or(*default)
+}
+
+/// The modelview and projection matrices something was drawn with.
+type Matrices = ([[f32; 3]; 3], [[f32; 3]; 3]);
+
+/// A LayerCache speeds up rendering Scenes where only a few elements change each frame, such as dashboards.
+///
+/// The static majority of the scene is rendered once into a texture. Each frame the texture is copied to the target
+/// and only the elements that changed (the volatile elements) are drawn over it. Elements become volatile when they
+/// change and stay volatile until the cache is rebuilt, which happens when the matrices, lens, or target size change,
+/// when everything is damaged, or when more than `max_volatile` of the elements are volatile. When an element
+/// becomes volatile, only the part of the texture it covered is redrawn without it.
+///
+/// Volatile elements are drawn after every static element, so a volatile edge may be drawn over a static node.
+pub struct LayerCache {
+    texture: Option<Texture2d>,
+    /// Where the part of the texture that volatile elements are taken out of is redrawn.
+    scratch: Option<Texture2d>,
+    matrices: Option<Matrices>,
+    lens: Option<Lens>,
+    volatile_nodes: HashSet<usize>,
+    volatile_edges: HashSet<usize>,
+    /// The view space bounds of the glow of every static element, as it was drawn into the texture.
+    node_bounds: HashMap<usize, [f32; 4]>,
+    edge_bounds: HashMap<usize, [f32; 4]>,
+    /// The fraction of the elements that may be volatile before the cache is rebuilt with all of them static.
+    pub max_volatile: f32,
+}
+
+impl Default for LayerCache {
+    fn default() -> LayerCache {
+        LayerCache::new()
+    }
+}
+
+impl LayerCache {
+    /// Make an empty cache that is built on the first render.
+    pub fn new() -> Self {
+        LayerCache {
+            texture: None,
+            scratch: None,
+            matrices: None,
+            lens: None,
+            volatile_nodes: HashSet::new(),
+            volatile_edges: HashSet::new(),
+            node_bounds: HashMap::new(),
+            edge_bounds: HashMap::new(),
+            max_volatile: 0.1,
+        }
+    }
+
+    /// Force the cache to be rebuilt on the next render.
+    pub fn invalidate(&mut self) {
+        self.texture = None;
+    }
+
+    /// Render a scene like `Scene::render`, redrawing only the elements that changed since the cache was built.
+    ///
+    /// The same cache should always be used with the same scene.
+    pub fn render<'a, F, D, S>(
+        &mut self,
+        facade: &F,
+        renderer: &Renderer<'a, D>,
+        scene: &mut Scene,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) -> Result<(), glium::texture::TextureCreationError>
+    where
+        F: glium::backend::Facade,
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        scene.update();
+        let damage = scene.take_damage();
+        let (width, height) = target.get_dimensions();
+
+        let resized = self.texture.as_ref().map(Texture2d::dimensions) != Some((width, height));
+        let mut rebuild = damage.all || resized || self.matrices != Some((modelview, projection))
+            || self.lens != renderer.lens();
+        if rebuild {
+            // Everything is drawn into the new cache, so only what changed this frame is volatile.
+            self.volatile_nodes.clear();
+            self.volatile_edges.clear();
+        }
+        // Elements that just became volatile were drawn into the cache in their old state.
+        let mut stale = None;
+        for i in damage.nodes {
+            if self.volatile_nodes.insert(i) {
+                stale = union(stale, self.node_bounds.remove(&i));
+            }
+        }
+        for i in damage.edges {
+            if self.volatile_edges.insert(i) {
+                stale = union(stale, self.edge_bounds.remove(&i));
+            }
+        }
+        let total = scene.node_vertices.len() + scene.vertex_edges.len();
+        let volatile = self.volatile_nodes.len() + self.volatile_edges.len();
+        if damage.all || volatile as f32 > self.max_volatile * total as f32 {
+            if !damage.all {
+                debug!("{} of {} elements changed, caching all of them", volatile, total);
+            }
+            self.volatile_nodes.clear();
+            self.volatile_edges.clear();
+            rebuild = true;
+        }
+
+        if rebuild {
+            trace!("rebuilding the static layer");
+            if resized {
+                self.texture = Some(Texture2d::empty(facade, width, height)?);
+            }
+            self.matrices = Some((modelview, projection));
+            self.lens = renderer.lens();
+            self.node_bounds = self.static_nodes(scene)
+                .map(|(i, node)| (i, glow_bounds(modelview, self.lens, slice::from_ref(node))))
+                .collect();
+            self.edge_bounds = self.static_edges(scene)
+                .map(|(i, pair)| (i, glow_bounds(modelview, self.lens, pair)))
+                .collect();
+            let texture = self.texture.as_ref().unwrap();
+            let mut layer = texture.as_surface();
+            self.draw_static(renderer, scene, &mut layer, modelview, projection, None);
+        } else if let Some(stale) = stale {
+            self.redraw(facade, renderer, scene, modelview, projection, stale)?;
+        }
+
+        let texture = self.texture.as_ref().unwrap();
+        texture.as_surface().fill(&*target, MagnifySamplerFilter::Nearest);
+        let volatile_nodes = scene
+            .vertex_nodes
+            .iter()
+            .zip(&scene.node_vertices)
+            .filter(|&(i, _)| self.volatile_nodes.contains(i))
+            .map(|(_, &n)| n)
+            .collect::<Vec<_>>();
+        let volatile_edges = scene
+            .vertex_edges
+            .iter()
+            .zip(scene.edge_vertices.chunks(2))
+            .filter(|&(i, _)| self.volatile_edges.contains(i))
+            .flat_map(|(_, pair)| pair.iter().cloned())
+            .collect::<Vec<_>>();
+        if !volatile_edges.is_empty() {
+            renderer.render_edges_round(target, modelview, projection, &volatile_edges);
+        }
+        if !volatile_nodes.is_empty() {
+            renderer.render_nodes(target, modelview, projection, &volatile_nodes);
+        }
+        Ok(())
+    }
+
+    /// Redraw the part of the texture under the view space bounds `stale` with only the static elements.
+    ///
+    /// The static elements that overlap it are drawn in their usual order into the scratch texture, and the pixels
+    /// under `stale` are copied back, which leaves them exactly as a full rebuild would.
+    fn redraw<'a, F, D>(
+        &mut self,
+        facade: &F,
+        renderer: &Renderer<'a, D>,
+        scene: &Scene,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        stale: [f32; 4],
+    ) -> Result<(), glium::texture::TextureCreationError>
+    where
+        F: glium::backend::Facade,
+        D: glium::backend::Facade,
+    {
+        let (width, height) = self.texture.as_ref().unwrap().dimensions();
+        let rect = match pixel_rect(projection, stale, width, height) {
+            Some(rect) => rect,
+            None => return Ok(()),
+        };
+        trace!("redrawing {}x{} pixels of the static layer", rect.width, rect.height);
+        if self.scratch.as_ref().map(Texture2d::dimensions) != Some((width, height)) {
+            self.scratch = Some(Texture2d::empty(facade, width, height)?);
+        }
+        let scratch = self.scratch.as_ref().unwrap();
+        self.draw_static(renderer, scene, &mut scratch.as_surface(), modelview, projection, Some(stale));
+        let texture = self.texture.as_ref().unwrap();
+        scratch.as_surface().blit_color(
+            &rect,
+            &texture.as_surface(),
+            &glium::BlitTarget {
+                left: rect.left,
+                bottom: rect.bottom,
+                width: rect.width as i32,
+                height: rect.height as i32,
+            },
+            MagnifySamplerFilter::Nearest,
+        );
+        Ok(())
+    }
+
+    /// Clear `layer` to the background and draw the static elements, or only those that overlap `within`.
+    fn draw_static<'a, D, S>(
+        &self,
+        renderer: &Renderer<'a, D>,
+        scene: &Scene,
+        layer: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+        within: Option<[f32; 4]>,
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        let overlaps = |bounds: Option<&[f32; 4]>| match (within, bounds) {
+            (Some(within), Some(bounds)) => {
+                bounds[0] <= within[2] && within[0] <= bounds[2] && bounds[1] <= within[3] && within[1] <= bounds[3]
+            }
+            _ => true,
+        };
+        let nodes = self.static_nodes(scene)
+            .filter(|&(i, _)| overlaps(self.node_bounds.get(&i)))
+            .map(|(_, &n)| n)
+            .collect::<Vec<_>>();
+        let edges = self.static_edges(scene)
+            .filter(|&(i, _)| overlaps(self.edge_bounds.get(&i)))
+            .flat_map(|(_, pair)| pair.iter().cloned())
+            .collect::<Vec<_>>();
+        let [r, g, b, a] = scene.theme.background;
+        layer.clear_color(r, g, b, a);
+        if !edges.is_empty() {
+            renderer.render_edges_round(layer, modelview, projection, &edges);
+        }
+        if !nodes.is_empty() {
+            renderer.render_nodes(layer, modelview, projection, &nodes);
+        }
+    }
+
+    /// Iterate over the index and vertex of every static node of a scene.
+    fn static_nodes<'s>(&'s self, scene: &'s Scene) -> impl Iterator<Item = (usize, &'s Node)> + 's {
+        scene
+            .vertex_nodes
+            .iter()
+            .cloned()
+            .zip(&scene.node_vertices)
+            .filter(move |&(i, _)| !self.volatile_nodes.contains(&i))
+    }
+
+    /// Iterate over the index and pair of vertices of every static edge of a scene.
+    fn static_edges<'s>(&'s self, scene: &'s Scene) -> impl Iterator<Item = (usize, &'s [Node])> + 's {
+        scene
+            .vertex_edges
+            .iter()
+            .cloned()
+            .zip(scene.edge_vertices.chunks(2))
+            .filter(move |&(i, _)| !self.volatile_edges.contains(&i))
+    }
+}
+
+/// Scale the alpha of every group of `per` vertices by the opacity of the group, only copying them if one changes.
+fn faded<F>(vertices: &[Node], per: usize, mut opacity: F) -> Cow<'_, [Node]>
+where
+    F: FnMut(usize) -> f32,
+{
+    let mut faded = Cow::Borrowed(vertices);
+    for group in 0..vertices.len() / per {
+        let opacity = opacity(group);
+        if opacity != 1.0 {
+            for node in &mut faded.to_mut()[group * per..(group + 1) * per] {
+                node.inner_color[3] *= opacity;
+                node.falloff_color[3] *= opacity;
+            }
+        }
+    }
+    faded
+}
+
+/// Get the view space bounds `[left, bottom, right, top]` that the glow of some vertices can reach.
+///
+/// The renderer covers the glow with triangles that reach out to less than three times the full radius of a vertex.
+/// Like `Renderer::render_tiled`, a vertex under the `lens` may be pulled anywhere else under it with its radii
+/// magnified by up to `strength + 1`.
+fn glow_bounds(modelview: [[f32; 3]; 3], lens: Option<Lens>, vertices: &[Node]) -> [f32; 4] {
+    let lens = lens.filter(|lens| lens.strength > 0.0 && lens.radius > 0.0);
+    let mut bounds = None;
+    for vertex in vertices {
+        let [x, y] = vertex.position;
+        let view = [
+            modelview[0][0] * x + modelview[1][0] * y + modelview[2][0],
+            modelview[0][1] * x + modelview[1][1] * y + modelview[2][1],
+        ];
+        let reach = 3.0 * (vertex.inner_radius + vertex.falloff_radius);
+        let mut covered = [view[0] - reach, view[1] - reach, view[0] + reach, view[1] + reach];
+        if let Some(lens) = lens {
+            let [cx, cy] = lens.center;
+            if (view[0] - cx).hypot(view[1] - cy) < lens.radius {
+                let reach = lens.radius + reach * (lens.strength + 1.0);
+                covered = [cx - reach, cy - reach, cx + reach, cy + reach];
+            }
+        }
+        bounds = union(bounds, Some(covered));
+    }
+    bounds.unwrap_or([::std::f32::INFINITY, ::std::f32::INFINITY, -::std::f32::INFINITY, -::std::f32::INFINITY])
+}
+
+/// Get the bounds that cover both `a` and `b`.
+fn union(a: Option<[f32; 4]>, b: Option<[f32; 4]>) -> Option<[f32; 4]> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some([a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Get the pixels of a `width` by `height` target that view space `bounds` cover, if any.
+fn pixel_rect(projection: [[f32; 3]; 3], bounds: [f32; 4], width: u32, height: u32) -> Option<glium::Rect> {
+    let mut ndc = [::std::f32::INFINITY, ::std::f32::INFINITY, -::std::f32::INFINITY, -::std::f32::INFINITY];
+    for &(x, y) in &[
+        (bounds[0], bounds[1]),
+        (bounds[2], bounds[1]),
+        (bounds[0], bounds[3]),
+        (bounds[2], bounds[3]),
+    ] {
+        let px = projection[0][0] * x + projection[1][0] * y + projection[2][0];
+        let py = projection[0][1] * x + projection[1][1] * y + projection[2][1];
+        ndc[0] = ndc[0].min(px);
+        ndc[1] = ndc[1].min(py);
+        ndc[2] = ndc[2].max(px);
+        ndc[3] = ndc[3].max(py);
+    }
+    if ndc[2] < -1.0 || ndc[0] > 1.0 || ndc[3] < -1.0 || ndc[1] > 1.0 {
+        return None;
+    }
+    // Round outwards by a pixel so that nothing the rasterizer touched is left behind.
+    let to_pixel = |v: f32, size: u32| ((v + 1.0) * 0.5 * size as f32).max(0.0).min(size as f32);
+    let left = (to_pixel(ndc[0], width).floor() as u32).saturating_sub(1);
+    let bottom = (to_pixel(ndc[1], height).floor() as u32).saturating_sub(1);
+    let right = (to_pixel(ndc[2], width).ceil() as u32 + 1).min(width);
+    let top = (to_pixel(ndc[3], height).ceil() as u32 + 1).min(height);
+    Some(glium::Rect {
+        left: left,
+        bottom: bottom,
+        width: right - left,
+        height: top - bottom,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    fn vertex(position: [f32; 2], radius: f32) -> Node {
+        Node {
+            position: position,
+            inner_color: [1.0; 4],
+            falloff: 0.0,
+            falloff_color: [0.0; 4],
+            falloff_radius: 0.0,
+            inner_radius: radius,
+        }
+    }
+
+    #[test]
+    fn glow_bounds_reach_past_the_triangles() {
+        let bounds = glow_bounds(IDENTITY, None, &[vertex([0.0, 0.0], 0.1), vertex([1.0, 0.5], 0.2)]);
+        assert_eq!(bounds, [-0.3, -0.3, 1.6, 1.1]);
+    }
+
+    #[test]
+    fn glow_bounds_cover_the_lens_under_it() {
+        let lens = Lens {
+            center: [0.0, 0.0],
+            radius: 0.5,
+            strength: 1.0,
+        };
+        // Outside of the lens nothing changes.
+        let outside = glow_bounds(IDENTITY, Some(lens), &[vertex([1.0, 0.0], 0.1)]);
+        assert_eq!(outside, glow_bounds(IDENTITY, None, &[vertex([1.0, 0.0], 0.1)]));
+        // Under it the vertex may end up anywhere under the lens with its reach doubled.
+        let under = glow_bounds(IDENTITY, Some(lens), &[vertex([0.2, 0.0], 0.1)]);
+        for (&bound, &expected) in under.iter().zip(&[-1.1, -1.1, 1.1, 1.1]) {
+            assert!((bound - expected).abs() < 1e-5);
+        }
+        // A lens without strength does nothing.
+        let weak = Lens {
+            strength: 0.0,
+            ..lens
+        };
+        let unlensed = glow_bounds(IDENTITY, Some(weak), &[vertex([0.2, 0.0], 0.1)]);
+        assert_eq!(unlensed, glow_bounds(IDENTITY, None, &[vertex([0.2, 0.0], 0.1)]));
+    }
+
+    #[test]
+    fn union_keeps_either_side() {
+        assert_eq!(union(None, None), None);
+        assert_eq!(union(Some([0.0, 0.0, 1.0, 1.0]), None), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(
+            union(Some([0.0, 0.0, 1.0, 1.0]), Some([-1.0, 0.5, 0.5, 2.0])),
+            Some([-1.0, 0.0, 1.0, 2.0])
+        );
+    }
+
+    #[test]
+    fn pixel_rect_rounds_outwards_and_clamps() {
+        let rect = pixel_rect(IDENTITY, [-0.5, -0.5, 0.5, 0.5], 100, 100).unwrap();
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (24, 24, 52, 52));
+        let rect = pixel_rect(IDENTITY, [-2.0, -2.0, 0.0, 2.0], 100, 100).unwrap();
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (0, 0, 51, 100));
+        assert!(pixel_rect(IDENTITY, [1.5, 1.5, 2.0, 2.0], 100, 100).is_none());
+    }
+
+    fn moved_scene() -> (Scene, usize, usize) {
+        let mut scene = Scene::default();
+        let a = scene.add_node(SceneNode::default());
+        let b = scene.add_node(SceneNode::default());
+        scene.set_history_limit(10);
+        (scene, a, b)
+    }
+
+    #[test]
+    fn consecutive_moves_of_a_node_are_one_step() {
+        let (mut scene, a, _) = moved_scene();
+        for i in 1..5 {
+            scene.set_node_position(a, [i as f32, 0.0]);
+        }
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [0.0, 0.0]);
+        assert!(scene.redo());
+        assert_eq!(scene.node(a).unwrap().position, [4.0, 0.0]);
+    }
+
+    #[test]
+    fn moves_of_other_nodes_are_separate_steps() {
+        let (mut scene, a, b) = moved_scene();
+        scene.set_node_position(a, [1.0, 0.0]);
+        scene.set_node_position(b, [2.0, 0.0]);
+        scene.set_node_position(a, [3.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [1.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(b).unwrap().position, [0.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn transactions_end_a_merged_move() {
+        let (mut scene, a, _) = moved_scene();
+        scene.set_node_position(a, [1.0, 0.0]);
+        scene.begin_transaction();
+        scene.set_node_position(a, [2.0, 0.0]);
+        scene.set_node_position(a, [3.0, 0.0]);
+        scene.commit_transaction();
+        scene.set_node_position(a, [4.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [3.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [1.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [0.0, 0.0]);
+        assert!(!scene.undo());
+    }
+
+    #[test]
+    fn a_batch_of_positions_is_one_step() {
+        let (mut scene, a, b) = moved_scene();
+        let handle = scene.handle();
+        handle.set_positions(vec![(a, [1.0, 0.0]), (b, [2.0, 0.0])]);
+        assert_eq!(scene.drain_updates(), 1);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [0.0, 0.0]);
+        assert_eq!(scene.node(b).unwrap().position, [0.0, 0.0]);
+        assert!(!scene.undo());
+    }
+
+    #[test]
+    fn batches_moving_the_same_nodes_are_merged() {
+        let (mut scene, a, b) = moved_scene();
+        let c = scene.add_node(SceneNode::default());
+        let handle = scene.handle();
+        for i in 1..20 {
+            handle.set_positions(vec![(a, [i as f32, 0.0]), (b, [0.0, i as f32])]);
+        }
+        handle.set_positions(vec![(a, [-1.0, 0.0])]);
+        handle.set_positions(vec![(a, [-2.0, 0.0]), (c, [3.0, 0.0])]);
+        scene.drain_updates();
+        // Moving a node the batches didn't move makes a new step.
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [-1.0, 0.0]);
+        assert_eq!(scene.node(c).unwrap().position, [0.0, 0.0]);
+        assert!(scene.undo());
+        assert_eq!(scene.node(a).unwrap().position, [0.0, 0.0]);
+        assert_eq!(scene.node(b).unwrap().position, [0.0, 0.0]);
+        // Only adding the third node is left.
+        assert!(scene.undo());
+        assert!(scene.node(c).is_none());
+        assert!(!scene.undo());
+    }
+}
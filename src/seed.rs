@@ -0,0 +1,87 @@
+/// A small, fast random number generator for the stochastic parts of layouts, jitter and palettes.
+///
+/// Functions like `layout::spectral_seeded` draw from an `Rng` made from a seed, so the same inputs and the same seed
+/// give exactly the same output on every platform, which keeps figures reproducible. It is a splitmix64 generator,
+/// which is not suitable for anything security related.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Make a generator from a seed. Every seed, including zero, gives a good sequence.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Get the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Get a number in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fill the mantissa exactly, so every value is equally likely and never reaches one.
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Get a number in `[low, high)`.
+    pub fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+
+    /// Shuffle a slice in place with a Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+        let a = (0..16).map(|_| a.next_u64()).collect::<Vec<_>>();
+        assert_eq!(a, (0..16).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(a, (0..16).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn zero_seed_is_not_degenerate() {
+        let mut rng = Rng::new(0);
+        let values = (0..4).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        assert!(values.iter().all(|&v| v != 0));
+        assert_ne!(values[0], values[1]);
+    }
+
+    #[test]
+    fn floats_stay_in_range() {
+        let mut rng = Rng::new(1);
+        for _ in 0..10_000 {
+            let f = rng.next_f32();
+            assert!((0.0..1.0).contains(&f));
+            let r = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&r));
+        }
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut items = (0..50).collect::<Vec<_>>();
+        Rng::new(3).shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use render2::{Node, QBezier};
+
+/// Make a smooth Catmull-Rom spline that passes through every waypoint, as a chain of quadratic bezier curves.
+///
+/// The style of each waypoint is interpolated along the spline. Each span between two waypoints is converted to a
+/// cubic bezier and approximated with two quadratic beziers, so it can be drawn with the qbezier renderers.
+/// Flat ended qbeziers meet without overlapping, which avoids bright spots where the curves join.
+pub fn catmull_rom(waypoints: &[Node]) -> Vec<QBezier> {
+    let len = waypoints.len();
+    if len < 2 {
+        return Vec::new();
+    }
+    let point = |i: isize| -> [f32; 2] {
+        // The end points are mirrored to get a tangent for the first and last spans.
+        if i < 0 {
+            let a = waypoints[0].position;
+            let b = waypoints[1].position;
+            [2.0 * a[0] - b[0], 2.0 * a[1] - b[1]]
+        } else if i as usize >= len {
+            let a = waypoints[len - 1].position;
+            let b = waypoints[len - 2].position;
+            [2.0 * a[0] - b[0], 2.0 * a[1] - b[1]]
+        } else {
+            waypoints[i as usize].position
+        }
+    };
+
+    let mut qbeziers = Vec::with_capacity(2 * (len - 1));
+    for i in 0..len - 1 {
+        let p0 = point(i as isize - 1);
+        let p1 = point(i as isize);
+        let p2 = point(i as isize + 1);
+        let p3 = point(i as isize + 2);
+        let c1 = add(p1, scale(sub(p2, p0), 1.0 / 6.0));
+        let c2 = sub(p2, scale(sub(p3, p1), 1.0 / 6.0));
+
+        // Split the cubic in half with de Casteljau's algorithm.
+        let m01 = lerp(p1, c1, 0.5);
+        let m12 = lerp(c1, c2, 0.5);
+        let m23 = lerp(c2, p2, 0.5);
+        let m012 = lerp(m01, m12, 0.5);
+        let m123 = lerp(m12, m23, 0.5);
+        let mid = lerp(m012, m123, 0.5);
+
+        let start = &waypoints[i];
+        let end = &waypoints[i + 1];
+        let mut middle = start.lerp(end, 0.5);
+        middle.position = mid;
+        qbeziers.push(qbezier(start, quadratic_control(p1, m01, m012, mid), &middle));
+        qbeziers.push(qbezier(&middle, quadratic_control(mid, m123, m23, p2), end));
+    }
+    qbeziers
+}
+
+/// How far `curve_edges` moves the control point of an edge away from its middle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curvature {
+    /// The same distance for every edge, so short edges bend more sharply than long ones.
+    Constant(f32),
+    /// A fraction of the length of each edge, so every edge bends into the same shape.
+    Proportional(f32),
+}
+
+/// The bit patterns of the two end points of an edge, the lesser first.
+type EndPoints = ([u32; 2], [u32; 2]);
+
+/// Turn straight edges (two nodes each) into qbeziers that bow to the left of their direction.
+///
+/// Edges between the same two nodes, such as the two directions of a reciprocal edge, are spread out instead of
+/// being drawn on top of each other. They alternate between the sides of the first of them and each pair bows out
+/// further than the one before. The curvature is the distance of the control point from the middle of the edge,
+/// and the curve itself reaches half of that. Edges of zero length stay straight.
+pub fn curve_edges(edges: &[Node], curvature: Curvature) -> Vec<QBezier> {
+    // The direction of the first edge between each pair of end points and how many edges were seen between them.
+    let mut seen: HashMap<EndPoints, ([f32; 2], usize)> = HashMap::new();
+    edges
+        .chunks(2)
+        .filter(|edge| edge.len() == 2)
+        .map(|edge| {
+            let (start, end) = (&edge[0], &edge[1]);
+            let bits = |p: [f32; 2]| [p[0].to_bits(), p[1].to_bits()];
+            let (a, b) = (bits(start.position), bits(end.position));
+            let key = if a <= b { (a, b) } else { (b, a) };
+            let direction = sub(end.position, start.position);
+            let entry = seen.entry(key).or_insert((direction, 0));
+            let index = entry.1;
+            entry.1 += 1;
+
+            let length = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+            let middle = lerp(start.position, end.position, 0.5);
+            if length == 0.0 {
+                return qbezier(start, middle, end);
+            }
+            // Alternate sides relative to the first edge, flipping for edges that go the other way.
+            let first = entry.0;
+            let same = first[0] * direction[0] + first[1] * direction[1] >= 0.0;
+            let side = if (index % 2 == 0) == same { 1.0 } else { -1.0 };
+            let level = (index / 2 + 1) as f32;
+            let distance = match curvature {
+                Curvature::Constant(distance) => distance,
+                Curvature::Proportional(fraction) => fraction * length,
+            };
+            let normal = [-direction[1] / length, direction[0] / length];
+            qbezier(start, add(middle, scale(normal, side * level * distance)), end)
+        })
+        .collect()
+}
+
+/// The control point of the quadratic bezier closest to a cubic bezier with the same end points.
+fn quadratic_control(p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2]) -> [f32; 2] {
+    scale(sub(scale(add(p1, p2), 3.0), add(p0, p3)), 0.25)
+}
+
+fn qbezier(start: &Node, control: [f32; 2], end: &Node) -> QBezier {
+    QBezier {
+        position0: start.position,
+        position1: control,
+        position2: end.position,
+        inner_color0: start.inner_color,
+        inner_color1: end.inner_color,
+        falloff_color0: start.falloff_color,
+        falloff_color1: end.falloff_color,
+        falloff0: start.falloff,
+        falloff1: end.falloff,
+        falloff_radius0: start.falloff_radius,
+        falloff_radius1: end.falloff_radius,
+        inner_radius0: start.inner_radius,
+        inner_radius1: end.inner_radius,
+    }
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: [f32; 2], s: f32) -> [f32; 2] {
+    [a[0] * s, a[1] * s]
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use style::Style;
+
+    fn nodes(positions: &[[f32; 2]]) -> Vec<Node> {
+        positions.iter().map(|&p| Style::default().node2(p)).collect()
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_every_waypoint() {
+        assert!(catmull_rom(&nodes(&[[0.0, 0.0]])).is_empty());
+        let waypoints = nodes(&[[0.0, 0.0], [1.0, 1.0], [2.0, 0.0]]);
+        let curves = catmull_rom(&waypoints);
+        assert_eq!(curves.len(), 4);
+        assert_eq!(curves[0].position0, [0.0, 0.0]);
+        assert_eq!(curves[1].position2, [1.0, 1.0]);
+        assert_eq!(curves[3].position2, [2.0, 0.0]);
+        for pair in curves.windows(2) {
+            assert_eq!(pair[0].position2, pair[1].position0);
+        }
+    }
+
+    #[test]
+    fn catmull_rom_keeps_straight_lines_straight() {
+        let curves = catmull_rom(&nodes(&[[0.0, 0.0], [1.0, 0.0], [3.0, 0.0]]));
+        assert!(curves.iter().all(|c| c.position1[1] == 0.0));
+    }
+
+    #[test]
+    fn curve_edges_bow_to_the_left() {
+        let curves = curve_edges(&nodes(&[[0.0, 0.0], [2.0, 0.0]]), Curvature::Constant(0.5));
+        assert_eq!(curves[0].position1, [1.0, 0.5]);
+        let curves = curve_edges(&nodes(&[[0.0, 0.0], [2.0, 0.0]]), Curvature::Proportional(0.5));
+        assert_eq!(curves[0].position1, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn curve_edges_spread_out_parallel_edges() {
+        let edges = nodes(&[
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 0.0],
+            [0.0, 0.0],
+            [0.0, 0.0],
+            [2.0, 0.0],
+        ]);
+        let controls = curve_edges(&edges, Curvature::Constant(0.5))
+            .iter()
+            .map(|c| c.position1)
+            .collect::<Vec<_>>();
+        assert_eq!(controls, vec![[1.0, 0.5], [1.0, -0.5], [1.0, 1.0]]);
+    }
+
+    #[test]
+    fn curve_edges_leave_zero_length_edges_straight() {
+        let curves = curve_edges(&nodes(&[[1.0, 1.0], [1.0, 1.0]]), Curvature::Constant(0.5));
+        assert_eq!(curves[0].position1, [1.0, 1.0]);
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::collections::hash_map;
+
+use render2;
+use render3;
+
+/// Style holds every glow parameter of a node or edge endpoint except its position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct Style {
+    pub inner_color: [f32; 4],
+    /// Decreasing falloff makes the nodes brightness more centered at the middle and increasing it makes it consistent.
+    pub falloff: f32,
+    pub falloff_color: [f32; 4],
+    pub falloff_radius: f32,
+    pub inner_radius: f32,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            inner_color: [1.0, 1.0, 1.0, 1.0],
+            falloff: 0.5,
+            falloff_color: [1.0, 1.0, 1.0, 1.0],
+            // These radii will only work in certian scenarios, but can be modified.
+            falloff_radius: 0.01,
+            inner_radius: 0.002,
+        }
+    }
+}
+
+impl Style {
+    /// Get a copy of this style with the color of its inner disc and glow replaced, keeping their transparency.
+    pub fn with_color(&self, color: [f32; 4]) -> Style {
+        let [r, g, b, _] = color;
+        Style {
+            inner_color: [r, g, b, self.inner_color[3] * color[3]],
+            falloff_color: [r, g, b, self.falloff_color[3] * color[3]],
+            ..*self
+        }
+    }
+
+    /// Make a 2d node at `position` with this style.
+    pub fn node2(&self, position: [f32; 2]) -> render2::Node {
+        render2::Node {
+            position: position,
+            inner_color: self.inner_color,
+            falloff: self.falloff,
+            falloff_color: self.falloff_color,
+            falloff_radius: self.falloff_radius,
+            inner_radius: self.inner_radius,
+        }
+    }
+
+    /// Make a rounded rectangle node at `position` with this style, where the inner radius rounds the corners.
+    pub fn rect(&self, position: [f32; 2], half_size: [f32; 2]) -> render2::RectNode {
+        render2::RectNode {
+            position: position,
+            half_size: half_size,
+            corner_radius: self.inner_radius,
+            inner_color: self.inner_color,
+            falloff: self.falloff,
+            falloff_color: self.falloff_color,
+            falloff_radius: self.falloff_radius,
+        }
+    }
+
+    /// Make a pie node at `position` with this style and the share and color of up to four segments.
+    ///
+    /// Panics if there are more than four segments.
+    pub fn pie(&self, position: [f32; 2], segments: &[(f32, [f32; 4])]) -> render2::PieNode {
+        let mut pie = render2::PieNode {
+            position: position,
+            shares: [0.0; 4],
+            color0: self.inner_color,
+            color1: self.inner_color,
+            color2: self.inner_color,
+            color3: self.inner_color,
+            hole: 0.0,
+            falloff: self.falloff,
+            falloff_color: self.falloff_color,
+            falloff_radius: self.falloff_radius,
+            inner_radius: self.inner_radius,
+        };
+        pie.set_segments(segments);
+        pie
+    }
+
+    /// Make a progress ring with this style around a node, where the inner radius is half of the ring's width.
+    pub fn ring(&self, around: &render2::Node, progress: f32) -> render2::RingNode {
+        render2::RingNode {
+            position: around.position,
+            // Leave a gap of half the width between the node and the ring.
+            radius: around.inner_radius + 2.0 * self.inner_radius,
+            width: 2.0 * self.inner_radius,
+            progress: progress,
+            color: self.inner_color,
+            track_color: [0.0; 4],
+            falloff: self.falloff,
+            falloff_color: self.falloff_color,
+            falloff_radius: self.falloff_radius,
+        }
+    }
+
+    /// Make a 3d node at `position` with this style.
+    pub fn node3(&self, position: [f32; 3]) -> render3::Node {
+        render3::Node {
+            position: position,
+            inner_color: self.inner_color,
+            falloff: self.falloff,
+            falloff_color: self.falloff_color,
+            falloff_radius: self.falloff_radius,
+            inner_radius: self.inner_radius,
+        }
+    }
+}
+
+impl From<render2::Node> for Style {
+    fn from(node: render2::Node) -> Style {
+        Style {
+            inner_color: node.inner_color,
+            falloff: node.falloff,
+            falloff_color: node.falloff_color,
+            falloff_radius: node.falloff_radius,
+            inner_radius: node.inner_radius,
+        }
+    }
+}
+
+impl From<render3::Node> for Style {
+    fn from(node: render3::Node) -> Style {
+        Style {
+            inner_color: node.inner_color,
+            falloff: node.falloff,
+            falloff_color: node.falloff_color,
+            falloff_radius: node.falloff_radius,
+            inner_radius: node.inner_radius,
+        }
+    }
+}
+
+/// A StyleSheet maps style names (like "server" or "error-edge") to styles.
+///
+/// Elements refer to styles by name and are resolved against a sheet when their vertices are built,
+/// so swapping the sheet changes the look of everything without touching per-element data.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct StyleSheet {
+    styles: HashMap<String, Style>,
+    fallback: Style,
+}
+
+impl StyleSheet {
+    /// Make an empty StyleSheet that resolves every name to the default style.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Make an empty StyleSheet that resolves unknown names to `fallback`.
+    pub fn with_fallback(fallback: Style) -> Self {
+        StyleSheet {
+            styles: HashMap::new(),
+            fallback: fallback,
+        }
+    }
+
+    /// Add or replace a named style, returning the previous style with that name.
+    pub fn insert<S>(&mut self, name: S, style: Style) -> Option<Style>
+    where
+        S: Into<String>,
+    {
+        self.styles.insert(name.into(), style)
+    }
+
+    /// Remove a named style, returning it if it existed.
+    pub fn remove(&mut self, name: &str) -> Option<Style> {
+        self.styles.remove(name)
+    }
+
+    /// Get a named style if it exists.
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        self.styles.get(name)
+    }
+
+    /// Get a mutable reference to a named style if it exists.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Style> {
+        self.styles.get_mut(name)
+    }
+
+    /// Get the style used for names that are not in the sheet.
+    pub fn fallback(&self) -> &Style {
+        &self.fallback
+    }
+
+    /// Set the style used for names that are not in the sheet.
+    pub fn set_fallback(&mut self, fallback: Style) {
+        self.fallback = fallback;
+    }
+
+    /// Find the style with a given name, using the fallback style if it does not exist.
+    pub fn resolve(&self, name: &str) -> Style {
+        self.styles.get(name).cloned().unwrap_or(self.fallback)
+    }
+
+    /// Iterate over all the named styles.
+    pub fn iter(&self) -> hash_map::Iter<'_, String, Style> {
+        self.styles.iter()
+    }
+}
@@ -0,0 +1,267 @@
+use glium::{self, Surface};
+
+use render2::{Node, Renderer};
+use style::Style;
+
+/// The span of time an element of a TemporalScene is visible for, from `start` up to but not including `end`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Interval {
+    /// An interval that starts at `start` and never ends.
+    pub fn from(start: f64) -> Interval {
+        Interval {
+            start: start,
+            end: ::std::f64::INFINITY,
+        }
+    }
+
+    /// Whether the interval includes a point in time.
+    pub fn contains(&self, time: f64) -> bool {
+        time >= self.start && time < self.end
+    }
+
+    /// How visible an element is at a point in time when it fades in and out over `fade` time.
+    fn opacity(&self, time: f64, fade: f64) -> f64 {
+        if !self.contains(time) {
+            0.0
+        } else if fade <= 0.0 {
+            1.0
+        } else {
+            ((time - self.start) / fade).min((self.end - time) / fade).min(1.0)
+        }
+    }
+}
+
+/// A TemporalScene holds nodes and edges that appear and disappear over time, such as communication logs or
+/// transactions, and renders the state of the graph at any point in time.
+///
+/// Time is in whatever units the data uses. Playback moves through it at `rate` units per second.
+pub struct TemporalScene {
+    nodes: Vec<(Interval, Node)>,
+    edges: Vec<(Interval, usize, usize, Style)>,
+    time: f64,
+    /// How many units of time pass per second while playing.
+    pub rate: f64,
+    /// How long elements take to fade in and out at the ends of their intervals, in units of time.
+    pub fade: f64,
+    playing: bool,
+}
+
+impl Default for TemporalScene {
+    fn default() -> TemporalScene {
+        TemporalScene::new()
+    }
+}
+
+impl TemporalScene {
+    /// Make an empty TemporalScene that is paused at time zero.
+    pub fn new() -> Self {
+        TemporalScene {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            time: 0.0,
+            rate: 1.0,
+            fade: 0.0,
+            playing: false,
+        }
+    }
+
+    /// Add a node that is visible during `interval` and get its index.
+    pub fn add_node(&mut self, interval: Interval, node: Node) -> usize {
+        self.nodes.push((interval, node));
+        self.nodes.len() - 1
+    }
+
+    /// Add an edge that is visible during `interval` while both of its nodes are visible as well.
+    ///
+    /// Panics if `from` or `to` is not the index of a node.
+    pub fn add_edge(&mut self, interval: Interval, from: usize, to: usize, style: Style) -> usize {
+        assert!(
+            from < self.nodes.len() && to < self.nodes.len(),
+            "glowygraph::TemporalScene::add_edge(): edge from {} to {} connects a node that is not in the scene",
+            from,
+            to
+        );
+        self.edges.push((interval, from, to, style));
+        self.edges.len() - 1
+    }
+
+    /// Get the earliest start and the latest finite end of every interval.
+    pub fn span(&self) -> Option<(f64, f64)> {
+        let intervals = self.nodes
+            .iter()
+            .map(|n| n.0)
+            .chain(self.edges.iter().map(|e| e.0));
+        intervals.fold(None, |span, interval| {
+            let end = if interval.end.is_finite() {
+                interval.end
+            } else {
+                interval.start
+            };
+            Some(match span {
+                Some((start, last)) => (interval.start.min(start), end.max(last)),
+                None => (interval.start, end),
+            })
+        })
+    }
+
+    /// Get the current time.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Jump to a point in time.
+    pub fn seek(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Start moving through time when `advance` is called.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Stop moving through time.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Whether the scene is playing.
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Move forward by `delta` seconds of playback if playing, pausing at the end of the span.
+    pub fn advance(&mut self, delta: f64) {
+        if !self.playing {
+            return;
+        }
+        self.time += delta * self.rate;
+        if let Some((_, end)) = self.span() {
+            if self.time >= end {
+                self.time = end;
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Get the nodes visible at a point in time.
+    pub fn nodes_at(&self, time: f64) -> Vec<Node> {
+        self.nodes
+            .iter()
+            .filter_map(|&(interval, node)| {
+                let opacity = interval.opacity(time, self.fade) as f32;
+                if opacity > 0.0 {
+                    Some(fade_node(node, opacity))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Get the edges visible at a point in time as pairs of nodes.
+    pub fn edges_at(&self, time: f64) -> Vec<Node> {
+        let mut edges = Vec::new();
+        for &(interval, from, to, style) in &self.edges {
+            let (from_interval, from) = self.nodes[from];
+            let (to_interval, to) = self.nodes[to];
+            let opacity = interval
+                .opacity(time, self.fade)
+                .min(from_interval.opacity(time, self.fade))
+                .min(to_interval.opacity(time, self.fade)) as f32;
+            if opacity > 0.0 {
+                edges.push(fade_node(style.node2(from.position), opacity));
+                edges.push(fade_node(style.node2(to.position), opacity));
+            }
+        }
+        edges
+    }
+
+    /// Draw the edges and then the nodes visible at the current time.
+    pub fn render<'a, D, S>(
+        &self,
+        renderer: &Renderer<'a, D>,
+        target: &mut S,
+        modelview: [[f32; 3]; 3],
+        projection: [[f32; 3]; 3],
+    ) where
+        D: glium::backend::Facade,
+        S: Surface,
+    {
+        let edges = self.edges_at(self.time);
+        if !edges.is_empty() {
+            renderer.render_edges_round(target, modelview, projection, &edges);
+        }
+        let nodes = self.nodes_at(self.time);
+        if !nodes.is_empty() {
+            renderer.render_nodes(target, modelview, projection, &nodes);
+        }
+    }
+}
+
+fn fade_node(mut node: Node, opacity: f32) -> Node {
+    node.inner_color[3] *= opacity;
+    node.falloff_color[3] *= opacity;
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node() -> Node {
+        Style::default().node2([0.0, 0.0])
+    }
+
+    #[test]
+    fn fades_at_both_ends_of_an_interval() {
+        let interval = Interval { start: 0.0, end: 4.0 };
+        assert_eq!(interval.opacity(-1.0, 1.0), 0.0);
+        assert_eq!(interval.opacity(0.5, 1.0), 0.5);
+        assert_eq!(interval.opacity(2.0, 1.0), 1.0);
+        assert_eq!(interval.opacity(3.5, 1.0), 0.5);
+        assert_eq!(interval.opacity(4.0, 1.0), 0.0);
+        assert_eq!(interval.opacity(0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn edges_need_both_nodes_visible() {
+        let mut scene = TemporalScene::new();
+        let a = scene.add_node(Interval::from(0.0), node());
+        let b = scene.add_node(Interval { start: 1.0, end: 2.0 }, node());
+        scene.add_edge(Interval::from(0.0), a, b, Style::default());
+        assert_eq!(scene.nodes_at(0.5).len(), 1);
+        assert!(scene.edges_at(0.5).is_empty());
+        assert_eq!(scene.edges_at(1.5).len(), 2);
+        assert!(scene.edges_at(2.5).is_empty());
+    }
+
+    #[test]
+    fn playback_stops_at_the_end_of_the_span() {
+        let mut scene = TemporalScene::new();
+        scene.add_node(Interval { start: 1.0, end: 3.0 }, node());
+        scene.add_node(Interval::from(2.0), node());
+        assert_eq!(scene.span(), Some((1.0, 3.0)));
+        scene.advance(1.0);
+        assert_eq!(scene.time(), 0.0);
+        scene.play();
+        scene.rate = 2.0;
+        scene.advance(1.0);
+        assert_eq!(scene.time(), 2.0);
+        scene.advance(1.0);
+        assert_eq!(scene.time(), 3.0);
+        assert!(!scene.playing());
+    }
+
+    #[test]
+    #[should_panic(expected = "connects a node that is not in the scene")]
+    fn edges_to_missing_nodes_panic() {
+        let mut scene = TemporalScene::new();
+        let a = scene.add_node(Interval::from(0.0), node());
+        scene.add_edge(Interval::from(0.0), a, a + 1, Style::default());
+    }
+}
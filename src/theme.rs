@@ -0,0 +1,106 @@
+use style::Style;
+
+/// A Theme is a complete visual language for a Scene.
+///
+/// It covers the background, the styles used by elements that don't name a style, and the style of selected elements.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default, deny_unknown_fields))]
+pub struct Theme {
+    pub background: [f32; 4],
+    pub node: Style,
+    pub edge: Style,
+    pub selection: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark_neon()
+    }
+}
+
+impl Theme {
+    /// Saturated cyan and magenta glows on a nearly black background.
+    pub fn dark_neon() -> Theme {
+        Theme {
+            background: [0.02, 0.02, 0.05, 1.0],
+            node: Style {
+                inner_color: [0.6, 1.0, 1.0, 1.0],
+                falloff: 0.25,
+                falloff_color: [0.0, 0.8, 1.0, 1.0],
+                falloff_radius: 0.02,
+                inner_radius: 0.01,
+            },
+            edge: Style {
+                inner_color: [1.0, 0.4, 1.0, 0.8],
+                falloff: 0.25,
+                falloff_color: [0.8, 0.0, 1.0, 0.6],
+                falloff_radius: 0.006,
+                inner_radius: 0.002,
+            },
+            selection: Style {
+                inner_color: [1.0, 1.0, 0.6, 1.0],
+                falloff: 0.5,
+                falloff_color: [1.0, 0.8, 0.0, 1.0],
+                falloff_radius: 0.03,
+                inner_radius: 0.012,
+            },
+        }
+    }
+
+    /// Dark, soft glows on a white background.
+    pub fn light() -> Theme {
+        Theme {
+            background: [0.97, 0.97, 0.95, 1.0],
+            node: Style {
+                inner_color: [0.1, 0.3, 0.6, 1.0],
+                falloff: 0.5,
+                falloff_color: [0.2, 0.4, 0.8, 0.6],
+                falloff_radius: 0.012,
+                inner_radius: 0.01,
+            },
+            edge: Style {
+                inner_color: [0.3, 0.3, 0.35, 0.9],
+                falloff: 0.5,
+                falloff_color: [0.4, 0.4, 0.5, 0.3],
+                falloff_radius: 0.004,
+                inner_radius: 0.002,
+            },
+            selection: Style {
+                inner_color: [0.8, 0.3, 0.0, 1.0],
+                falloff: 0.5,
+                falloff_color: [1.0, 0.5, 0.0, 0.7],
+                falloff_radius: 0.02,
+                inner_radius: 0.012,
+            },
+        }
+    }
+
+    /// Opaque white and yellow on pure black with tight glows so that shapes stay distinguishable.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            background: [0.0, 0.0, 0.0, 1.0],
+            node: Style {
+                inner_color: [1.0, 1.0, 1.0, 1.0],
+                falloff: 2.0,
+                falloff_color: [1.0, 1.0, 1.0, 1.0],
+                falloff_radius: 0.004,
+                inner_radius: 0.012,
+            },
+            edge: Style {
+                inner_color: [1.0, 1.0, 1.0, 1.0],
+                falloff: 2.0,
+                falloff_color: [1.0, 1.0, 1.0, 1.0],
+                falloff_radius: 0.001,
+                inner_radius: 0.003,
+            },
+            selection: Style {
+                inner_color: [1.0, 1.0, 0.0, 1.0],
+                falloff: 2.0,
+                falloff_color: [1.0, 1.0, 0.0, 1.0],
+                falloff_radius: 0.006,
+                inner_radius: 0.016,
+            },
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::ops::Range;
+
+use glium;
+
+use render2::Node;
+use routing::Rect;
+
+/// A tile of a Tiles, which covers a square cell of the grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    /// The column and row of the cell in the grid.
+    pub cell: [i32; 2],
+    /// The bounding box of the positions of the vertices in the tile, without their glow.
+    pub bounds: Rect,
+    /// The largest distance the glow of a vertex in the tile reaches from its position, which is in view space
+    /// like the radii of the vertices.
+    pub radius: f32,
+    /// The range of vertices in the buffer that belong to the tile.
+    pub range: Range<usize>,
+}
+
+/// Tiles store the vertices of nodes or edges on the GPU grouped into square tiles of the plane.
+///
+/// Elements are put in the tile that contains their center, and the vertices of each tile are contiguous in the buffer.
+/// Culling then only needs one bounds check per tile, and tiles that are next to each other in a row of the grid
+/// are drawn with a single draw call, so panning and zooming stay fast with millions of elements as long as only
+/// a part of them is on screen. Tiles are ordered by row and then by column.
+pub struct Tiles {
+    buffer: glium::VertexBuffer<Node>,
+    tiles: Vec<Tile>,
+    size: f32,
+}
+
+impl Tiles {
+    /// Upload nodes grouped into tiles that are `size` wide.
+    pub fn nodes<F>(facade: &F, nodes: &[Node], size: f32) -> Result<Self, glium::vertex::BufferCreationError>
+    where
+        F: glium::backend::Facade,
+    {
+        Tiles::build(facade, nodes, 1, size)
+    }
+
+    /// Upload edges (two nodes per edge) grouped into tiles that are `size` wide.
+    pub fn edges<F>(facade: &F, edges: &[Node], size: f32) -> Result<Self, glium::vertex::BufferCreationError>
+    where
+        F: glium::backend::Facade,
+    {
+        Tiles::build(facade, edges, 2, size)
+    }
+
+    fn build<F>(
+        facade: &F,
+        vertices: &[Node],
+        stride: usize,
+        size: f32,
+    ) -> Result<Self, glium::vertex::BufferCreationError>
+    where
+        F: glium::backend::Facade,
+    {
+        let (sorted, tiles) = group(vertices, stride, size);
+        debug!("grouped {} vertices into {} tiles", sorted.len(), tiles.len());
+
+        Ok(Tiles {
+            buffer: glium::VertexBuffer::new(facade, &sorted)?,
+            tiles: tiles,
+            size: size,
+        })
+    }
+
+    /// Get the buffer holding the vertices of every tile.
+    pub fn buffer(&self) -> &glium::VertexBuffer<Node> {
+        &self.buffer
+    }
+
+    /// Get every tile, ordered by row and then by column.
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    /// Get the width of the tiles.
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Get the ranges of the buffer that must be drawn to show everything that overlaps `view`.
+    ///
+    /// `view` is in the same space as the positions, while `scale` is how many units of view space one unit of the
+    /// positions is, which is the scale of the modelview matrix. Since the radii are in view space, the glow of tiles
+    /// reaches further past their positions the further out the view is zoomed. Ranges of tiles that are next to each
+    /// other in the buffer are merged, so each range is a single draw call.
+    pub fn visible(&self, view: &Rect, scale: f32) -> Vec<Range<usize>> {
+        visible(&self.tiles, view, scale)
+    }
+}
+
+/// Sort the elements of `vertices` into tiles that are `size` wide, getting the sorted vertices and the tiles.
+fn group(vertices: &[Node], stride: usize, size: f32) -> (Vec<Node>, Vec<Tile>) {
+    let cell = |element: &[Node]| {
+        let count = element.len() as f32;
+        let x = element.iter().map(|n| n.position[0]).sum::<f32>() / count;
+        let y = element.iter().map(|n| n.position[1]).sum::<f32>() / count;
+        [(x / size).floor() as i32, (y / size).floor() as i32]
+    };
+    let mut elements = vertices.chunks(stride).collect::<Vec<_>>();
+    elements.sort_by_key(|&element| {
+        let [x, y] = cell(element);
+        (y, x)
+    });
+
+    let mut sorted = Vec::with_capacity(vertices.len());
+    let mut tiles: Vec<Tile> = Vec::new();
+    for element in elements {
+        let start = sorted.len();
+        sorted.extend_from_slice(element);
+        let bounds = element
+            .iter()
+            .map(|n| Rect {
+                min: n.position,
+                max: n.position,
+            })
+            .fold(None, |acc: Option<Rect>, r| Some(acc.map_or(r, |acc| acc.union(&r))))
+            .unwrap();
+        // The falloff starts at the edge of the inner disc, so the glow reaches the sum of the radii.
+        let radius = element
+            .iter()
+            .map(|n| n.inner_radius + n.falloff_radius)
+            .fold(0.0, f32::max);
+        let cell = cell(element);
+        match tiles.last_mut() {
+            Some(ref mut tile) if tile.cell == cell => {
+                tile.bounds = tile.bounds.union(&bounds);
+                tile.radius = tile.radius.max(radius);
+                tile.range.end = sorted.len();
+                continue;
+            }
+            _ => {}
+        }
+        tiles.push(Tile {
+            cell: cell,
+            bounds: bounds,
+            radius: radius,
+            range: start..sorted.len(),
+        });
+    }
+    (sorted, tiles)
+}
+
+fn visible(tiles: &[Tile], view: &Rect, scale: f32) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let overlaps = |tile: &&Tile| tile.bounds.expand(tile.radius / scale).intersects(view);
+    for tile in tiles.iter().filter(overlaps) {
+        match ranges.last_mut() {
+            Some(ref mut range) if range.end == tile.range.start => {
+                range.end = tile.range.end;
+                continue;
+            }
+            _ => {}
+        }
+        ranges.push(tile.range.clone());
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Point2;
+
+    fn node(x: f32, y: f32, radius: f32) -> Node {
+        let mut node = Node::from(Point2::new(x, y));
+        node.inner_radius = radius;
+        node.falloff_radius = radius;
+        node
+    }
+
+    #[test]
+    fn groups_elements_by_cell() {
+        let nodes = [node(1.5, 0.5, 0.0), node(0.5, 0.5, 0.0), node(0.25, 0.75, 0.0), node(0.5, 1.5, 0.0)];
+        let (sorted, tiles) = group(&nodes, 1, 1.0);
+        assert_eq!(sorted.len(), 4);
+        assert_eq!(tiles.iter().map(|t| t.cell).collect::<Vec<_>>(), vec![[0, 0], [1, 0], [0, 1]]);
+        assert_eq!(tiles[0].range, 0..2);
+        assert_eq!(tiles[0].bounds.min, [0.25, 0.5]);
+        assert_eq!(tiles[0].bounds.max, [0.5, 0.75]);
+    }
+
+    #[test]
+    fn glow_reaches_further_when_zoomed_out() {
+        let (_, tiles) = group(&[node(0.5, 0.5, 0.05)], 1, 1.0);
+        let view = Rect {
+            min: [1.0, 0.0],
+            max: [2.0, 1.0],
+        };
+        // The glow reaches 0.1 in view space, which is 0.1 units of the positions at a scale of one and 1.0 at 0.1.
+        assert!(visible(&tiles, &view, 1.0).is_empty());
+        assert_eq!(visible(&tiles, &view, 0.1), vec![0..1]);
+    }
+
+    #[test]
+    fn merges_adjacent_ranges() {
+        let nodes = [node(0.5, 0.5, 0.0), node(1.5, 0.5, 0.0), node(5.5, 0.5, 0.0)];
+        let (_, tiles) = group(&nodes, 1, 1.0);
+        let view = Rect {
+            min: [0.0, 0.0],
+            max: [10.0, 1.0],
+        };
+        assert_eq!(visible(&tiles, &view, 1.0), vec![0..3]);
+        let view = Rect {
+            min: [0.0, 0.0],
+            max: [1.0, 1.0],
+        };
+        assert_eq!(visible(&tiles, &view, 1.0), vec![0..1]);
+    }
+}
@@ -0,0 +1,27 @@
+use glium::uniforms::{UniformValue, Uniforms};
+
+/// Uniforms with extra named values supplied by the caller added after them.
+///
+/// Values that no shader in the program declares are ignored, so the stock shaders draw as usual.
+pub(crate) struct ExtraUniforms<'u, U>
+where
+    U: 'u,
+{
+    pub uniforms: &'u U,
+    pub extra: &'u [(&'u str, UniformValue<'u>)],
+}
+
+impl<'u, U> Uniforms for ExtraUniforms<'u, U>
+where
+    U: Uniforms,
+{
+    fn visit_values<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&str, UniformValue<'a>),
+    {
+        self.uniforms.visit_values(&mut f);
+        for &(name, value) in self.extra {
+            f(name, value);
+        }
+    }
+}
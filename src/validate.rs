@@ -0,0 +1,357 @@
+use std::error::Error;
+use std::fmt;
+
+use render2;
+use render3;
+
+/// What is wrong with a field of an element passed to a renderer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The field contains NaN or infinity.
+    NotFinite,
+    /// A size, radius, or falloff is negative.
+    Negative,
+    /// A color component or progress is outside of `0.0..=1.0`.
+    OutOfRange,
+    /// An edge or curve starts and ends at the same position and has no direction.
+    ZeroLength,
+}
+
+/// A problem with a field of one element in a slice passed to a renderer.
+///
+/// Problems like these don't cause errors on the GPU, they just make elements invisible or cover the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// The index of the element in the slice, which is the index of the first node for edges.
+    pub index: usize,
+    /// The name of the offending field.
+    pub field: &'static str,
+    pub problem: Problem,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let problem = match self.problem {
+            Problem::NotFinite => "is NaN or infinite",
+            Problem::Negative => "is negative",
+            Problem::OutOfRange => "is outside of 0 to 1",
+            Problem::ZeroLength => "is the same at both ends",
+        };
+        write!(f, "element {}: {} {}", self.index, self.field, problem)
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Elements that can be checked before they are passed to a renderer.
+pub trait Validate {
+    /// Add every problem with this element, which is at `index`, to `errors`.
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>);
+}
+
+impl Validate for render2::Node {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position", &self.position);
+        check.color("inner_color", &self.inner_color);
+        check.color("falloff_color", &self.falloff_color);
+        check.non_negative("falloff", self.falloff);
+        check.non_negative("falloff_radius", self.falloff_radius);
+        check.non_negative("inner_radius", self.inner_radius);
+    }
+}
+
+impl Validate for render3::Node {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position", &self.position);
+        check.color("inner_color", &self.inner_color);
+        check.color("falloff_color", &self.falloff_color);
+        check.non_negative("falloff", self.falloff);
+        check.non_negative("falloff_radius", self.falloff_radius);
+        check.non_negative("inner_radius", self.inner_radius);
+    }
+}
+
+impl Validate for render2::QBezier {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position0", &self.position0);
+        check.finite("position1", &self.position1);
+        check.finite("position2", &self.position2);
+        check.color("inner_color0", &self.inner_color0);
+        check.color("inner_color1", &self.inner_color1);
+        check.color("falloff_color0", &self.falloff_color0);
+        check.color("falloff_color1", &self.falloff_color1);
+        check.non_negative("falloff0", self.falloff0);
+        check.non_negative("falloff1", self.falloff1);
+        check.non_negative("falloff_radius0", self.falloff_radius0);
+        check.non_negative("falloff_radius1", self.falloff_radius1);
+        check.non_negative("inner_radius0", self.inner_radius0);
+        check.non_negative("inner_radius1", self.inner_radius1);
+        if self.position0 == self.position1 && self.position1 == self.position2 {
+            check.fail("position2", Problem::ZeroLength);
+        }
+    }
+}
+
+impl Validate for render2::RectNode {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position", &self.position);
+        if check.finite("half_size", &self.half_size) && self.half_size.iter().any(|&h| h < 0.0) {
+            check.fail("half_size", Problem::Negative);
+        }
+        check.non_negative("corner_radius", self.corner_radius);
+        check.color("inner_color", &self.inner_color);
+        check.color("falloff_color", &self.falloff_color);
+        check.non_negative("falloff", self.falloff);
+        check.non_negative("falloff_radius", self.falloff_radius);
+    }
+}
+
+impl Validate for render2::PieNode {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position", &self.position);
+        if check.finite("shares", &self.shares) && self.shares.iter().any(|&s| s < 0.0) {
+            check.fail("shares", Problem::Negative);
+        }
+        check.color("color0", &self.color0);
+        check.color("color1", &self.color1);
+        check.color("color2", &self.color2);
+        check.color("color3", &self.color3);
+        check.non_negative("hole", self.hole);
+        check.non_negative("falloff", self.falloff);
+        check.color("falloff_color", &self.falloff_color);
+        check.non_negative("falloff_radius", self.falloff_radius);
+        check.non_negative("inner_radius", self.inner_radius);
+    }
+}
+
+impl Validate for render2::RingNode {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position", &self.position);
+        check.non_negative("radius", self.radius);
+        check.non_negative("width", self.width);
+        if check.finite("progress", &[self.progress]) && (self.progress < 0.0 || self.progress > 1.0) {
+            check.fail("progress", Problem::OutOfRange);
+        }
+        check.color("color", &self.color);
+        check.color("track_color", &self.track_color);
+        check.non_negative("falloff", self.falloff);
+        check.color("falloff_color", &self.falloff_color);
+        check.non_negative("falloff_radius", self.falloff_radius);
+    }
+}
+
+impl Validate for render2::Ribbon {
+    fn validate(&self, index: usize, errors: &mut Vec<ValidationError>) {
+        let mut check = Check {
+            index: index,
+            errors: errors,
+        };
+        check.finite("position0", &self.position0);
+        check.finite("position1", &self.position1);
+        check.finite("position2", &self.position2);
+        check.non_negative("width0", self.width0);
+        check.non_negative("width1", self.width1);
+        check.color("color0", &self.color0);
+        check.color("color1", &self.color1);
+        if check.finite("fill", &[self.fill]) && (self.fill < 0.0 || self.fill > 1.0) {
+            check.fail("fill", Problem::OutOfRange);
+        }
+        check.non_negative("falloff_radius", self.falloff_radius);
+        if self.position0 == self.position2 {
+            check.fail("position2", Problem::ZeroLength);
+        }
+    }
+}
+
+/// Check every element of a slice of nodes or curves.
+pub fn validate<T>(elements: &[T]) -> Vec<ValidationError>
+where
+    T: Validate,
+{
+    let mut errors = Vec::new();
+    for (i, element) in elements.iter().enumerate() {
+        element.validate(i, &mut errors);
+    }
+    errors
+}
+
+/// Check every node of a slice of 2d edges, as well as that no edge has zero length.
+pub fn validate_edges2(edges: &[render2::Node]) -> Vec<ValidationError> {
+    let mut errors = validate(edges);
+    zero_length(edges.chunks(2).map(|e| e.iter().map(|n| n.position)), 2, &mut errors);
+    errors
+}
+
+/// Check every node of a slice of 3d edges, as well as that no edge has zero length.
+pub fn validate_edges3(edges: &[render3::Node]) -> Vec<ValidationError> {
+    let mut errors = validate(edges);
+    zero_length(edges.chunks(2).map(|e| e.iter().map(|n| n.position)), 2, &mut errors);
+    errors
+}
+
+/// Check every node of a 2d polyline, as well as that no segment has zero length.
+pub fn validate_polyline2(polyline: &[render2::Node]) -> Vec<ValidationError> {
+    let mut errors = validate(polyline);
+    zero_length(polyline.windows(2).map(|s| s.iter().map(|n| n.position)), 1, &mut errors);
+    errors
+}
+
+/// Log every error if there are any and tell whether there were none, which the renderers check when validation is
+/// enabled to skip drawing invalid elements.
+pub(crate) fn report(errors: &[ValidationError]) -> bool {
+    if !errors.is_empty() {
+        let report = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n");
+        warn!("skipping invalid elements passed to the renderer:\n{}", report);
+    }
+    errors.is_empty()
+}
+
+/// Report segments whose two end positions are identical, where the segments start every `step` elements.
+pub(crate) fn zero_length<S, I, P>(segments: S, step: usize, errors: &mut Vec<ValidationError>)
+where
+    S: Iterator<Item = I>,
+    I: Iterator<Item = P>,
+    P: PartialEq,
+{
+    for (i, mut segment) in segments.enumerate() {
+        if let (Some(a), Some(b)) = (segment.next(), segment.next()) {
+            if a == b {
+                errors.push(ValidationError {
+                    index: i * step,
+                    field: "position",
+                    problem: Problem::ZeroLength,
+                });
+            }
+        }
+    }
+}
+
+struct Check<'e> {
+    index: usize,
+    errors: &'e mut Vec<ValidationError>,
+}
+
+impl<'e> Check<'e> {
+    fn fail(&mut self, field: &'static str, problem: Problem) {
+        self.errors.push(ValidationError {
+            index: self.index,
+            field: field,
+            problem: problem,
+        });
+    }
+
+    fn finite(&mut self, field: &'static str, values: &[f32]) -> bool {
+        if values.iter().all(|v| v.is_finite()) {
+            true
+        } else {
+            self.fail(field, Problem::NotFinite);
+            false
+        }
+    }
+
+    fn color(&mut self, field: &'static str, color: &[f32; 4]) {
+        if self.finite(field, color) && color.iter().any(|&c| c < 0.0 || c > 1.0) {
+            self.fail(field, Problem::OutOfRange);
+        }
+    }
+
+    fn non_negative(&mut self, field: &'static str, value: f32) {
+        if self.finite(field, &[value]) && value < 0.0 {
+            self.fail(field, Problem::Negative);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(position: [f32; 2]) -> render2::Node {
+        render2::Node {
+            position: position,
+            inner_color: [1.0; 4],
+            falloff: 0.5,
+            falloff_color: [1.0; 4],
+            falloff_radius: 0.1,
+            inner_radius: 0.05,
+        }
+    }
+
+    fn error(index: usize, field: &'static str, problem: Problem) -> ValidationError {
+        ValidationError {
+            index: index,
+            field: field,
+            problem: problem,
+        }
+    }
+
+    #[test]
+    fn valid_nodes_have_no_errors() {
+        assert!(validate(&[node([0.0, 0.0]), node([1.0, 1.0])]).is_empty());
+        assert!(report(&[]));
+    }
+
+    #[test]
+    fn not_finite_fields_are_reported() {
+        let mut bad = node([0.0, 0.0]);
+        bad.position[1] = ::std::f32::NAN;
+        bad.inner_radius = ::std::f32::INFINITY;
+        assert_eq!(
+            validate(&[node([0.0, 0.0]), bad]),
+            vec![error(1, "position", Problem::NotFinite), error(1, "inner_radius", Problem::NotFinite)]
+        );
+    }
+
+    #[test]
+    fn negative_sizes_are_reported() {
+        let mut bad = node([0.0, 0.0]);
+        bad.falloff_radius = -1.0;
+        assert_eq!(validate(&[bad]), vec![error(0, "falloff_radius", Problem::Negative)]);
+    }
+
+    #[test]
+    fn colors_out_of_range_are_reported() {
+        let mut bad = node([0.0, 0.0]);
+        bad.falloff_color[3] = 1.5;
+        bad.inner_color[0] = -0.1;
+        assert_eq!(
+            validate(&[node([0.0, 0.0]), node([1.0, 0.0]), bad]),
+            vec![error(2, "inner_color", Problem::OutOfRange), error(2, "falloff_color", Problem::OutOfRange)]
+        );
+        assert!(!report(&validate(&[bad])));
+    }
+
+    #[test]
+    fn zero_length_edges_are_reported_at_their_first_node() {
+        let edges = [node([0.0, 0.0]), node([1.0, 0.0]), node([2.0, 2.0]), node([2.0, 2.0])];
+        assert_eq!(validate_edges2(&edges), vec![error(2, "position", Problem::ZeroLength)]);
+        // A polyline checks every segment, so the same nodes have a problem between the last two.
+        assert_eq!(validate_polyline2(&edges), vec![error(2, "position", Problem::ZeroLength)]);
+        let polyline = [node([0.0, 0.0]), node([0.0, 0.0]), node([1.0, 0.0])];
+        assert_eq!(validate_polyline2(&polyline), vec![error(0, "position", Problem::ZeroLength)]);
+    }
+}
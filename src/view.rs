@@ -0,0 +1,265 @@
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+use glium;
+use glium::texture::Texture2d;
+
+use program;
+use render2::{Node, Renderer};
+use routing::Rect;
+use scene::Scene;
+
+/// Whether a VisibleElement is a node or an edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Node,
+    Edge,
+}
+
+/// An element of a Scene that is on screen, as reported by `GraphView::visible_elements`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibleElement {
+    pub kind: ElementKind,
+    /// The index of the node or edge in the Scene.
+    pub index: usize,
+    /// The bounding box of the element including its glow, in pixels from the top left of the widget.
+    ///
+    /// It is not clipped to the widget, so it may extend past its edges.
+    pub bounds: Rect,
+    /// The name of the style the element asked for.
+    pub style: Option<String>,
+    pub selected: bool,
+    /// Whether the element is the node that was under the pointer when it last moved.
+    pub hovered: bool,
+}
+
+/// A pointer event over a GraphView, in pixels relative to the top left of the widget.
+///
+/// GUI toolkits report these for the image widget the texture of the GraphView is shown in, and they are forwarded
+/// to `GraphView::pointer` unchanged.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PointerEvent {
+    Moved { x: f32, y: f32 },
+    /// The primary button was pressed.
+    Pressed { x: f32, y: f32 },
+    /// The primary button was released.
+    Released { x: f32, y: f32 },
+    /// The wheel was scrolled, where positive `lines` zoom in.
+    Scrolled { x: f32, y: f32, lines: f32 },
+    /// The pointer left the widget.
+    Left,
+}
+
+/// A GraphView renders a Scene into its own texture so it can be embedded in a GUI as an image widget.
+///
+/// The texture can be registered with a GUI toolkit's glium backend (such as `imgui-glium-renderer`) and shown as
+/// an image. The pointer events the toolkit reports over the image are forwarded back with `pointer`, which picks,
+/// selects, pans and zooms, or with `hover` and `click` for toolkits that handle panning themselves.
+/// Pointer positions are in pixels relative to the top left of the widget.
+pub struct GraphView {
+    texture: Texture2d,
+    /// The modelview matrix used to draw the scene, which can be changed to pan and zoom.
+    pub modelview: [[f32; 3]; 3],
+    hovered: Option<usize>,
+    /// Where the primary button was pressed and whether the pointer has been dragged since.
+    drag: Option<([f32; 2], bool)>,
+}
+
+impl GraphView {
+    /// Make a GraphView with a texture of the given size in pixels.
+    pub fn new<F>(facade: &F, width: u32, height: u32) -> Self
+    where
+        F: glium::backend::Facade,
+    {
+        GraphView {
+            texture: program::allocate(Texture2d::empty(facade, width, height), "the view texture"),
+            modelview: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    /// Change the size of the texture if it is different, which should be done when the widget is resized.
+    pub fn resize<F>(&mut self, facade: &F, width: u32, height: u32)
+    where
+        F: glium::backend::Facade,
+    {
+        if self.texture.dimensions() != (width, height) {
+            self.texture = program::allocate(Texture2d::empty(facade, width, height), "the view texture");
+        }
+    }
+
+    /// Get the texture that the scene is rendered into.
+    pub fn texture(&self) -> &Texture2d {
+        &self.texture
+    }
+
+    /// Get the projection matrix that corrects for the aspect ratio of the widget.
+    pub fn projection(&self) -> [[f32; 3]; 3] {
+        let (width, height) = self.texture.dimensions();
+        let hscale = height as f32 / width as f32;
+        [[hscale, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    /// Render the scene into the texture.
+    pub fn render<'a, D>(&self, renderer: &Renderer<'a, D>, scene: &mut Scene)
+    where
+        D: glium::backend::Facade,
+    {
+        let mut surface = self.texture.as_surface();
+        scene.render(renderer, &mut surface, self.modelview, self.projection());
+    }
+
+    /// Convert a pointer position over the widget into the world space of the scene.
+    pub fn to_world(&self, x: f32, y: f32) -> [f32; 2] {
+        let (width, height) = self.texture.dimensions();
+        let ndc = Vector3::new(2.0 * x / width as f32 - 1.0, 1.0 - 2.0 * y / height as f32, 1.0);
+        let transform = Matrix3::from(self.projection()) * Matrix3::from(self.modelview);
+        match transform.invert() {
+            Some(inverse) => {
+                let world = inverse * ndc;
+                [world.x, world.y]
+            }
+            None => [0.0, 0.0],
+        }
+    }
+
+    /// Get every node and edge that is at least partly inside the widget, nodes first, with its bounds in pixels.
+    ///
+    /// This is meant for building accessibility trees and hit regions in a DOM overlay over the widget.
+    /// The scene is updated first so the bounds match what is drawn by `render`.
+    pub fn visible_elements(&self, scene: &mut Scene) -> Vec<VisibleElement> {
+        scene.update();
+        let (width, height) = self.texture.dimensions();
+        let screen = Rect {
+            min: [0.0, 0.0],
+            max: [width as f32, height as f32],
+        };
+        let modelview = Matrix3::from(self.modelview);
+        let projection = self.projection();
+        // Radii are in view space, so only the projection scales them.
+        let scale = [
+            projection[0][0].hypot(projection[0][1]) * 0.5 * width as f32,
+            projection[1][0].hypot(projection[1][1]) * 0.5 * height as f32,
+        ];
+        let transform = Matrix3::from(projection);
+        let node_bounds = |node: &Node| {
+            let view = modelview * Vector3::new(node.position[0], node.position[1], 1.0);
+            let ndc = transform * Vector3::new(view.x, view.y, 1.0);
+            let center = [
+                0.5 * (ndc.x + 1.0) * width as f32,
+                0.5 * (1.0 - ndc.y) * height as f32,
+            ];
+            let radius = node.inner_radius + node.falloff_radius;
+            Rect {
+                min: [center[0] - radius * scale[0], center[1] - radius * scale[1]],
+                max: [center[0] + radius * scale[0], center[1] + radius * scale[1]],
+            }
+        };
+
+        let mut elements = Vec::new();
+        for (index, node) in scene.nodes() {
+            if let Some(vertex) = scene.node_vertex(index) {
+                let bounds = node_bounds(vertex);
+                if bounds.intersects(&screen) {
+                    elements.push(VisibleElement {
+                        kind: ElementKind::Node,
+                        index: index,
+                        bounds: bounds,
+                        style: node.style.clone(),
+                        selected: node.selected,
+                        hovered: self.hovered == Some(index),
+                    });
+                }
+            }
+        }
+        for (index, edge) in scene.edges() {
+            if let Some(pair) = scene.edge_vertex_pair(index) {
+                let bounds = node_bounds(&pair[0]).union(&node_bounds(&pair[1]));
+                if bounds.intersects(&screen) {
+                    elements.push(VisibleElement {
+                        kind: ElementKind::Edge,
+                        index: index,
+                        bounds: bounds,
+                        style: edge.style.clone(),
+                        selected: edge.selected,
+                        hovered: false,
+                    });
+                }
+            }
+        }
+        elements
+    }
+
+    /// Forward a pointer move and get the node under the pointer, if any.
+    pub fn hover(&mut self, scene: &Scene, x: f32, y: f32) -> Option<usize> {
+        self.hovered = scene.pick(self.modelview, self.to_world(x, y)).map(|(node, _)| node);
+        self.hovered
+    }
+
+    /// Get the node that was under the pointer when it last moved.
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// Forward a click, toggling the selection of the node under the pointer and returning it.
+    pub fn click(&mut self, scene: &mut Scene, x: f32, y: f32) -> Option<usize> {
+        let node = self.hover(scene, x, y);
+        if let Some(node) = node {
+            let selected = scene.node(node).map(|n| n.selected).unwrap_or(false);
+            scene.set_node_selected(node, !selected);
+        }
+        node
+    }
+
+    /// Forward a pointer event from the GUI toolkit and get the node under the pointer, if any.
+    ///
+    /// Moving hovers nodes, clicking a node toggles its selection, dragging pans the view and scrolling zooms it
+    /// around the pointer.
+    pub fn pointer(&mut self, scene: &mut Scene, event: PointerEvent) -> Option<usize> {
+        match event {
+            PointerEvent::Moved { x, y } => {
+                if let Some((last, _)) = self.drag {
+                    let (from, to) = (self.to_view(last[0], last[1]), self.to_view(x, y));
+                    self.modelview[2][0] += to[0] - from[0];
+                    self.modelview[2][1] += to[1] - from[1];
+                    self.drag = Some(([x, y], true));
+                }
+                self.hover(scene, x, y)
+            }
+            PointerEvent::Pressed { x, y } => {
+                self.drag = Some(([x, y], false));
+                self.hover(scene, x, y)
+            }
+            PointerEvent::Released { x, y } => match self.drag.take() {
+                Some((_, false)) => self.click(scene, x, y),
+                _ => self.hover(scene, x, y),
+            },
+            PointerEvent::Scrolled { x, y, lines } => {
+                let center = self.to_view(x, y);
+                let factor = 1.1f32.powf(lines);
+                for column in 0..2 {
+                    self.modelview[column][0] *= factor;
+                    self.modelview[column][1] *= factor;
+                }
+                // Scale around the pointer so the point under it stays put.
+                self.modelview[2][0] = center[0] + factor * (self.modelview[2][0] - center[0]);
+                self.modelview[2][1] = center[1] + factor * (self.modelview[2][1] - center[1]);
+                self.hover(scene, x, y)
+            }
+            PointerEvent::Left => {
+                self.drag = None;
+                self.hovered = None;
+                None
+            }
+        }
+    }
+
+    /// Convert a pointer position over the widget into view space, which is before the projection is applied.
+    fn to_view(&self, x: f32, y: f32) -> [f32; 2] {
+        let (width, height) = self.texture.dimensions();
+        let projection = self.projection();
+        [
+            (2.0 * x / width as f32 - 1.0) / projection[0][0],
+            (1.0 - 2.0 * y / height as f32) / projection[1][1],
+        ]
+    }
+}
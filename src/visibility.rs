@@ -0,0 +1,194 @@
+/// Visibility is a bitset of which elements in a vertex buffer are shown, for filtering without rebuilding buffers.
+///
+/// Pass it to the `_filtered` methods of the Renderer along with a vertex buffer that is kept between frames.
+/// Only the indices of the visible elements are uploaded when drawing, so changing a filter (such as hiding edges
+/// with a weight below a threshold) costs a pass over the bits instead of rebuilding the vertices.
+/// Every element is visible when it is made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Visibility {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl Visibility {
+    /// Make a Visibility for `len` elements, which are all visible.
+    pub fn new(len: usize) -> Self {
+        let mut visibility = Visibility {
+            bits: vec![!0; (len + 63) / 64],
+            len: len,
+        };
+        visibility.trim();
+        visibility
+    }
+
+    /// Get the number of elements, whether they are visible or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no elements at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of visible elements.
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// Whether an element is visible, which is false for elements past the end.
+    pub fn is_visible(&self, element: usize) -> bool {
+        element < self.len && self.bits[element / 64] & (1 << (element % 64)) != 0
+    }
+
+    /// Show or hide an element.
+    ///
+    /// Panics if the element is past the end.
+    pub fn set(&mut self, element: usize, visible: bool) {
+        assert!(element < self.len, "element {} is past the end of {} elements", element, self.len);
+        if visible {
+            self.bits[element / 64] |= 1 << (element % 64);
+        } else {
+            self.bits[element / 64] &= !(1 << (element % 64));
+        }
+    }
+
+    /// Show an element.
+    pub fn show(&mut self, element: usize) {
+        self.set(element, true);
+    }
+
+    /// Hide an element.
+    pub fn hide(&mut self, element: usize) {
+        self.set(element, false);
+    }
+
+    /// Show every element.
+    pub fn show_all(&mut self) {
+        for b in &mut self.bits {
+            *b = !0;
+        }
+        self.trim();
+    }
+
+    /// Hide every element.
+    pub fn hide_all(&mut self) {
+        for b in &mut self.bits {
+            *b = 0;
+        }
+    }
+
+    /// Show exactly the elements for which `visible` returns true, such as `|i| weights[i] >= threshold`.
+    pub fn filter<F>(&mut self, mut visible: F)
+    where
+        F: FnMut(usize) -> bool,
+    {
+        for element in 0..self.len {
+            self.set(element, visible(element));
+        }
+    }
+
+    /// Change the number of elements, where added elements are visible.
+    pub fn resize(&mut self, len: usize) {
+        let old = self.len;
+        self.bits.resize((len + 63) / 64, !0);
+        self.len = len;
+        for element in old..len {
+            self.set(element, true);
+        }
+        self.trim();
+    }
+
+    /// Iterate over the visible elements in order.
+    pub fn iter<'v>(&'v self) -> Box<dyn Iterator<Item = usize> + 'v> {
+        Box::new(self.bits.iter().enumerate().flat_map(|(word, &bits)| {
+            (0..64)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| word * 64 + bit)
+        }))
+    }
+
+    /// Get the vertex indices of the visible elements, where every element has `vertices` consecutive vertices.
+    pub(crate) fn indices(&self, vertices: usize) -> Vec<u32> {
+        let mut indices = Vec::with_capacity(self.count() * vertices);
+        for element in self.iter() {
+            let first = element * vertices;
+            indices.extend((first..first + vertices).map(|i| i as u32));
+        }
+        indices
+    }
+
+    /// Clear the bits past the end so they are never counted.
+    fn trim(&mut self) {
+        let used = self.len % 64;
+        if used != 0 {
+            if let Some(last) = self.bits.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_with_everything_visible() {
+        let visibility = Visibility::new(70);
+        assert_eq!(visibility.len(), 70);
+        assert_eq!(visibility.count(), 70);
+        assert!(visibility.is_visible(69));
+        assert!(!visibility.is_visible(70));
+        assert!(Visibility::new(0).is_empty());
+    }
+
+    #[test]
+    fn hiding_and_showing() {
+        let mut visibility = Visibility::new(130);
+        visibility.hide_all();
+        assert_eq!(visibility.count(), 0);
+        visibility.show(3);
+        visibility.show(64);
+        visibility.show(129);
+        assert_eq!(visibility.iter().collect::<Vec<_>>(), vec![3, 64, 129]);
+        visibility.hide(64);
+        assert_eq!(visibility.count(), 2);
+        visibility.show_all();
+        assert_eq!(visibility.count(), 130);
+    }
+
+    #[test]
+    fn filter_keeps_matching_elements() {
+        let mut visibility = Visibility::new(10);
+        visibility.filter(|i| i % 3 == 0);
+        assert_eq!(visibility.iter().collect::<Vec<_>>(), vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn resize_shows_new_elements_and_drops_old_ones() {
+        let mut visibility = Visibility::new(60);
+        visibility.hide(59);
+        visibility.resize(70);
+        assert!(!visibility.is_visible(59));
+        assert_eq!(visibility.count(), 69);
+        visibility.resize(5);
+        assert_eq!(visibility.count(), 5);
+        visibility.resize(64);
+        assert_eq!(visibility.count(), 64);
+    }
+
+    #[test]
+    fn indices_cover_every_vertex_of_visible_elements() {
+        let mut visibility = Visibility::new(4);
+        visibility.hide(1);
+        visibility.hide(2);
+        assert_eq!(visibility.indices(2), vec![0, 1, 6, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end")]
+    fn setting_past_the_end_panics() {
+        Visibility::new(3).show(3);
+    }
+}
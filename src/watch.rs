@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use load::{LoadError, SceneDescription};
+use scene::Scene;
+
+/// A SceneWatcher monitors a DOT or JSON scene file and reloads it into a Scene whenever it changes.
+///
+/// Nodes that keep their id between versions of the file glide from their old position to their new one,
+/// which makes the crate usable as a live preview for external graph generators.
+pub struct SceneWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    /// How long the transition between two versions of the file takes in seconds.
+    pub transition_duration: f32,
+    ids: HashMap<String, usize>,
+    transition: Vec<(usize, [f32; 2], [f32; 2])>,
+    elapsed: f32,
+}
+
+impl SceneWatcher {
+    /// Make a SceneWatcher for a file, which will be loaded on the first poll.
+    ///
+    /// The format is chosen from the extension (`.json` is JSON and anything else is DOT).
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        SceneWatcher {
+            path: path.into(),
+            modified: None,
+            transition_duration: 0.5,
+            ids: HashMap::new(),
+            transition: Vec::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Check whether the file changed and, if it did, replace the contents of `scene` with it.
+    ///
+    /// Returns whether the scene was reloaded. A file that fails to parse is reported once and then
+    /// ignored until it is modified again, leaving the scene as it was.
+    pub fn poll(&mut self, scene: &mut Scene) -> Result<bool, LoadError> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(false);
+        }
+        self.modified = Some(modified);
+        let description = SceneDescription::load(&self.path).map_err(|e| {
+            warn!("failed to reload {}: {}", self.path.display(), e);
+            e
+        })?;
+        info!(
+            "reloaded {} with {} nodes and {} edges",
+            self.path.display(),
+            description.nodes.len(),
+            description.edges.len()
+        );
+
+        let old_positions = self.ids
+            .iter()
+            .filter_map(|(id, &index)| scene.node(index).map(|n| (id.clone(), n.position)))
+            .collect::<HashMap<_, _>>();
+
+        // The whole reload is one step of history.
+        scene.begin_transaction();
+        scene.clear();
+        let indices = description.add_to(scene);
+        self.ids = description
+            .nodes
+            .iter()
+            .map(|n| n.id.clone())
+            .zip(indices)
+            .collect();
+
+        self.transition.clear();
+        self.elapsed = 0.0;
+        for (id, &index) in &self.ids {
+            if let Some(&from) = old_positions.get(id) {
+                let to = scene.node(index).unwrap().position;
+                self.transition.push((index, from, to));
+                scene.set_node_position(index, from);
+            }
+        }
+        scene.commit_transaction();
+        Ok(true)
+    }
+
+    /// Get the scene index of the node with a given id in the file.
+    pub fn index(&self, id: &str) -> Option<usize> {
+        self.ids.get(id).cloned()
+    }
+
+    /// Whether nodes are still moving to their new positions.
+    pub fn transitioning(&self) -> bool {
+        !self.transition.is_empty()
+    }
+
+    /// Advance the transition to the latest version of the file by `delta` seconds.
+    pub fn animate(&mut self, scene: &mut Scene, delta: f32) {
+        if self.transition.is_empty() {
+            return;
+        }
+        self.elapsed += delta;
+        let t = if self.transition_duration > 0.0 {
+            (self.elapsed / self.transition_duration).min(1.0)
+        } else {
+            1.0
+        };
+        // Smoothstep so that nodes ease in and out.
+        let t = t * t * (3.0 - 2.0 * t);
+        // Every frame is a transaction that only moves the same nodes, so the whole transition is merged into one step.
+        scene.begin_transaction();
+        for &(index, from, to) in &self.transition {
+            scene.set_node_position(
+                index,
+                [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t],
+            );
+        }
+        scene.commit_transaction();
+        if self.elapsed >= self.transition_duration {
+            self.transition.clear();
+        }
+    }
+}